@@ -0,0 +1,61 @@
+//! Compares single threaded and parallel decoding of the bundled fixtures.
+//!
+//! Run with `cargo bench --features rayon`.
+
+use std::{
+    io::Cursor,
+    time::{Duration, Instant},
+};
+
+use eff::edds::{DecodeOptions, RawEdds};
+
+const INPUT_PATH_PREFIX: &str = "./tests/test-data/edds_in/";
+const FIXTURES: [&str; 4] = [
+    "Eden_1337_supertexture",
+    "Eden_1337_normal",
+    "car_bc7",
+    "uaz_rgba",
+];
+const ITERATIONS: u32 = 10;
+
+fn time<F>(mut decode: F) -> Duration
+where
+    F: FnMut(),
+{
+    // Warm up caches and the rayon thread pool.
+    decode();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        decode();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let options = DecodeOptions::default();
+
+    println!(
+        "{:<24} {:>12} {:>12} {:>9}",
+        "fixture", "serial ms", "parallel ms", "speed-up"
+    );
+    for name in FIXTURES {
+        let file = std::fs::read(format!("{}{}.edds", INPUT_PATH_PREFIX, name)).unwrap();
+        let raw = RawEdds::from(&mut Cursor::new(&file)).unwrap();
+
+        let serial = time(|| {
+            raw.decode(&options).unwrap();
+        });
+        let parallel = time(|| {
+            raw.par_decode(&options).unwrap();
+        });
+
+        println!(
+            "{:<24} {:>12.2} {:>12.2} {:>8.2}x",
+            name,
+            serial.as_secs_f64() * 1000.0,
+            parallel.as_secs_f64() * 1000.0,
+            serial.as_secs_f64() / parallel.as_secs_f64()
+        );
+    }
+}
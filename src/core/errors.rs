@@ -19,6 +19,95 @@ pub enum EddsError {
     #[error("Unknown image data type: `{0}`!\nPlease report this error at https://github.com/aff-org/eff/issues")]
     UnknownImageDataType(String),
 
+    #[error("Mipmap index {0} is out of range, the texture has {1} mipmaps")]
+    MipmapIndexOutOfRange(usize, usize),
+
+    #[error("Surface index {0} is out of range, the texture has {1} surfaces")]
+    SurfaceIndexOutOfRange(usize, usize),
+
+    #[error("Expected a texture with a single surface but it has {0}")]
+    MultipleSurfaces(usize),
+
+    #[error("Encoding to `{0}` is not supported")]
+    UnsupportedEncodeFormat(String),
+
+    #[error("Expected {0} bytes of RGBA8 pixel data but got {1}")]
+    PixelDataSizeMismatch(usize, usize),
+
+    #[error("Image size {0}x{1} is invalid, width and height must be at least 1")]
+    InvalidImageSize(usize, usize),
+
+    #[error("Image size {0}x{1} is too large, its data size overflows")]
+    ImageTooLarge(usize, usize),
+
+    #[error("Expected at least {0} bytes of mip data but got {1}")]
+    MipDataTooShort(usize, usize),
+
+    #[error("LZ4 failed")]
+    Lz4Error(#[from] lzzzz::Error),
+
+    #[error("Invalid LZ4 stream at byte {position}: {reason}")]
+    InvalidLz4Stream { position: usize, reason: String },
+
+    #[error("Header is truncated at offset {offset}, expected {expected} bytes but got {actual}")]
+    TruncatedHeader {
+        offset: u64,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error("Mipmap {index} at offset {offset} is truncated, expected {expected} bytes but got {actual}")]
+    TruncatedMip {
+        index: usize,
+        offset: u64,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error("Mipmap {index} at offset {offset} failed to decompress")]
+    MipDecompression {
+        index: usize,
+        offset: u64,
+        #[source]
+        source: Box<EddsError>,
+    },
+
+    #[error("Mipmap {index} at offset {offset} holds {actual} bytes, expected {expected}")]
+    MipSizeMismatch {
+        index: usize,
+        offset: u64,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error("Header failed validation: {0:?}")]
+    InvalidHeader(Vec<HeaderDiagnostic>),
+
     #[error("unknown decoding error")]
     Unknown,
 }
+
+/// Flag fields of the header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderField {
+    Flags,
+    PixelFormatFlags,
+    Caps,
+    Caps2,
+}
+
+/// Problem found in a header that doesn't stop it from being decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeaderDiagnostic {
+    /// Bits that aren't a known flag of `field`.
+    UnknownBits { field: HeaderField, bits: u32 },
+    /// A flag the header needs for the data it describes is missing, given as
+    /// its `DdsHeaderFlags` bits.
+    MissingFlag(u32),
+    /// `pitch_or_linear_size` doesn't match the size of the largest mip.
+    PitchOrLinearSize { expected: u32, actual: u32 },
+    /// The header has several mips but the caps don't say so.
+    MissingMipmapCaps { mip_map_count: u32 },
+    /// The pixel format size isn't 32.
+    PixelFormatSize(u32),
+}
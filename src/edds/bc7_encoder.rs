@@ -0,0 +1,288 @@
+//! BC7 encoder using mode 6 only.
+//!
+//! Mode 6 stores a single RGBA line per block with 7 bit endpoints, a shared
+//! p-bit per endpoint and 4 bit indices. That covers opaque and alpha textures
+//! alike and is the mode most encoders spend most blocks in.
+
+use super::EncodeQuality;
+
+const WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+type Pixel = [f32; 4];
+
+#[derive(Debug, Clone, Copy)]
+struct Endpoints {
+    /// 7 bit values per channel.
+    colors: [[u8; 4]; 2],
+    p_bits: [u8; 2],
+}
+
+impl Endpoints {
+    fn unquantize(&self, endpoint: usize) -> [u32; 4] {
+        self.colors[endpoint].map(|c| ((c as u32) << 1) | self.p_bits[endpoint] as u32)
+    }
+
+    fn palette(&self) -> [[u32; 4]; 16] {
+        let (e0, e1) = (self.unquantize(0), self.unquantize(1));
+        let mut palette = [[0; 4]; 16];
+        for (entry, &weight) in palette.iter_mut().zip(WEIGHTS_4.iter()) {
+            *entry = std::array::from_fn(|c| ((64 - weight) * e0[c] + weight * e1[c] + 32) >> 6);
+        }
+        palette
+    }
+}
+
+fn quantize_endpoint(color: &Pixel, p_bit: u8) -> [u8; 4] {
+    color.map(|c| ((c - p_bit as f32) / 2.0).round().clamp(0.0, 127.0) as u8)
+}
+
+fn distance(pixel: &Pixel, color: &[u32; 4]) -> f32 {
+    pixel
+        .iter()
+        .zip(color.iter())
+        .map(|(&p, &c)| (p - c as f32) * (p - c as f32))
+        .sum()
+}
+
+/// Picks the closest palette entry for every pixel, returns the indices and the error.
+fn assign_indices(pixels: &[Pixel], endpoints: &Endpoints) -> ([u8; 16], f32) {
+    let palette = endpoints.palette();
+    let mut indices = [0; 16];
+    let mut error = 0.0;
+
+    for (index, pixel) in indices.iter_mut().zip(pixels.iter()) {
+        let (best, best_error) = palette
+            .iter()
+            .enumerate()
+            .map(|(i, color)| (i, distance(pixel, color)))
+            .fold((0, f32::MAX), |best, candidate| {
+                if candidate.1 < best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            });
+        *index = best as u8;
+        error += best_error;
+    }
+
+    (indices, error)
+}
+
+/// Endpoints along the principal axis of the pixels.
+fn principal_axis_endpoints(pixels: &[Pixel]) -> (Pixel, Pixel) {
+    let count = pixels.len() as f32;
+    let mut mean = [0.0; 4];
+    for pixel in pixels {
+        for (mean, &value) in mean.iter_mut().zip(pixel.iter()) {
+            *mean += value / count;
+        }
+    }
+
+    let mut covariance = [[0.0_f32; 4]; 4];
+    for pixel in pixels {
+        let d: Pixel = std::array::from_fn(|c| pixel[c] - mean[c]);
+        for (i, row) in covariance.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value += d[i] * d[j];
+            }
+        }
+    }
+
+    // Power iteration, starting from the diagonal keeps it stable for grey blocks.
+    let mut axis = [
+        covariance[0][0],
+        covariance[1][1],
+        covariance[2][2],
+        covariance[3][3],
+    ];
+    for _ in 0..8 {
+        let mut next = [0.0; 4];
+        for (value, row) in next.iter_mut().zip(covariance.iter()) {
+            *value = row.iter().zip(axis.iter()).map(|(a, b)| a * b).sum();
+        }
+        let length = next.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if length < 1e-6 {
+            break;
+        }
+        axis = next.map(|v| v / length);
+    }
+
+    let length = axis.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if length < 1e-6 {
+        return (mean, mean);
+    }
+    let axis = axis.map(|v| v / length);
+
+    let (mut min, mut max) = (f32::MAX, f32::MIN);
+    for pixel in pixels {
+        let t: f32 = (0..4).map(|c| (pixel[c] - mean[c]) * axis[c]).sum();
+        min = min.min(t);
+        max = max.max(t);
+    }
+
+    let endpoint =
+        |t: f32| -> Pixel { std::array::from_fn(|c| (mean[c] + axis[c] * t).clamp(0.0, 255.0)) };
+    (endpoint(min), endpoint(max))
+}
+
+/// Quantizes the endpoints, trying every p-bit combination when `search_p_bits` is set.
+fn quantize(
+    pixels: &[Pixel],
+    e0: &Pixel,
+    e1: &Pixel,
+    search_p_bits: bool,
+) -> (Endpoints, [u8; 16], f32) {
+    let combinations: &[[u8; 2]] = if search_p_bits {
+        &[[0, 0], [0, 1], [1, 0], [1, 1]]
+    } else {
+        &[[0, 0], [1, 1]]
+    };
+
+    let mut best: Option<(Endpoints, [u8; 16], f32)> = None;
+    for &p_bits in combinations {
+        let endpoints = Endpoints {
+            colors: [
+                quantize_endpoint(e0, p_bits[0]),
+                quantize_endpoint(e1, p_bits[1]),
+            ],
+            p_bits,
+        };
+        let (indices, error) = assign_indices(pixels, &endpoints);
+        if best.as_ref().map_or(true, |best| error < best.2) {
+            best = Some((endpoints, indices, error));
+        }
+    }
+
+    best.unwrap()
+}
+
+/// Least squares endpoints for fixed indices.
+fn refine_endpoints(pixels: &[Pixel], indices: &[u8]) -> Option<(Pixel, Pixel)> {
+    let (mut aa, mut ab, mut bb) = (0.0, 0.0, 0.0);
+    let (mut ax, mut bx) = ([0.0; 4], [0.0; 4]);
+
+    for (pixel, &index) in pixels.iter().zip(indices.iter()) {
+        let b = WEIGHTS_4[index as usize] as f32 / 64.0;
+        let a = 1.0 - b;
+        aa += a * a;
+        ab += a * b;
+        bb += b * b;
+        for ((ax, bx), &value) in ax.iter_mut().zip(bx.iter_mut()).zip(pixel.iter()) {
+            *ax += a * value;
+            *bx += b * value;
+        }
+    }
+
+    let determinant = aa * bb - ab * ab;
+    if determinant.abs() < 1e-6 {
+        return None;
+    }
+
+    let e0 = std::array::from_fn(|c| ((bb * ax[c] - ab * bx[c]) / determinant).clamp(0.0, 255.0));
+    let e1 = std::array::from_fn(|c| ((aa * bx[c] - ab * ax[c]) / determinant).clamp(0.0, 255.0));
+    Some((e0, e1))
+}
+
+struct BitWriter {
+    value: u128,
+    position: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, bits: u32) {
+        self.value |= (value as u128 & ((1 << bits) - 1)) << self.position;
+        self.position += bits;
+    }
+}
+
+fn pack_block(endpoints: &Endpoints, indices: &[u8; 16]) -> [u8; 16] {
+    let mut endpoints = *endpoints;
+    let mut indices = *indices;
+
+    // The anchor index is stored without its top bit, swap the endpoints if it is set.
+    if indices[0] & 0x8 != 0 {
+        endpoints.colors.swap(0, 1);
+        endpoints.p_bits.swap(0, 1);
+        for index in indices.iter_mut() {
+            *index = 15 - *index;
+        }
+    }
+
+    let mut writer = BitWriter {
+        value: 0,
+        position: 0,
+    };
+    writer.write(1 << 6, 7);
+    for (&c0, &c1) in endpoints.colors[0].iter().zip(endpoints.colors[1].iter()) {
+        writer.write(c0 as u32, 7);
+        writer.write(c1 as u32, 7);
+    }
+    writer.write(endpoints.p_bits[0] as u32, 1);
+    writer.write(endpoints.p_bits[1] as u32, 1);
+    writer.write(indices[0] as u32, 3);
+    for &index in indices[1..].iter() {
+        writer.write(index as u32, 4);
+    }
+
+    writer.value.to_le_bytes()
+}
+
+fn encode_block(pixels: &[Pixel], quality: EncodeQuality) -> [u8; 16] {
+    let (e0, e1) = principal_axis_endpoints(pixels);
+    let search_p_bits = quality != EncodeQuality::Fast;
+    let mut best = quantize(pixels, &e0, &e1, search_p_bits);
+
+    let iterations = match quality {
+        EncodeQuality::Fast => 0,
+        EncodeQuality::Normal => 1,
+        EncodeQuality::Slow => 4,
+    };
+    for _ in 0..iterations {
+        let (e0, e1) = match refine_endpoints(pixels, &best.1) {
+            Some(endpoints) => endpoints,
+            None => break,
+        };
+        let candidate = quantize(pixels, &e0, &e1, search_p_bits);
+        if candidate.2 >= best.2 {
+            break;
+        }
+        best = candidate;
+    }
+
+    pack_block(&best.0, &best.1)
+}
+
+/// Encodes a `width` x `height` RGBA8 image to BC7 blocks.
+pub(crate) fn encode_bc7(
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    quality: EncodeQuality,
+) -> Vec<u8> {
+    let (blocks_x, blocks_y) = (width.div_ceil(4), height.div_ceil(4));
+    let mut output = Vec::with_capacity(blocks_x * blocks_y * 16);
+    let mut pixels = Vec::with_capacity(16);
+
+    for block_y in 0..blocks_y {
+        for block_x in 0..blocks_x {
+            // Pixels outside the image repeat the last row or column.
+            pixels.clear();
+            for y in block_y * 4..block_y * 4 + 4 {
+                for x in block_x * 4..block_x * 4 + 4 {
+                    let (x, y) = (x.min(width - 1), y.min(height - 1));
+                    let offset = (y * width + x) * 4;
+                    pixels.push([
+                        rgba[offset] as f32,
+                        rgba[offset + 1] as f32,
+                        rgba[offset + 2] as f32,
+                        rgba[offset + 3] as f32,
+                    ]);
+                }
+            }
+            output.extend(encode_block(&pixels, quality));
+        }
+    }
+
+    output
+}
@@ -0,0 +1,728 @@
+//! Block decoders for the BCn variants the decoding crates don't cover.
+
+/// Calls `f` with every block of a `width` x `height` surface together with the
+/// pixel coordinates of its top left corner.
+fn for_each_block<F>(src: &[u8], width: usize, height: usize, block_size: usize, mut f: F)
+where
+    F: FnMut(&[u8], usize, usize),
+{
+    let blocks_wide = width.div_ceil(4);
+    let blocks_high = height.div_ceil(4);
+
+    for (i, block) in src
+        .chunks_exact(block_size)
+        .take(blocks_wide * blocks_high)
+        .enumerate()
+    {
+        f(block, (i % blocks_wide) * 4, (i / blocks_wide) * 4);
+    }
+}
+
+/// Decodes one 8 byte BC4 channel block into 16 values normalised to `[0, 1]`
+/// for UNORM or `[-1, 1]` for SNORM data.
+fn decode_bc4_channel(block: &[u8], signed: bool) -> [f32; 16] {
+    let (r0, r1, max) = if signed {
+        // -128 and -127 both map to -1.0
+        (
+            (block[0] as i8).max(-127) as f32,
+            (block[1] as i8).max(-127) as f32,
+            127.0,
+        )
+    } else {
+        (block[0] as f32, block[1] as f32, 255.0)
+    };
+
+    let mut palette = [r0, r1, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+    if r0 > r1 {
+        for i in 1..7 {
+            palette[i + 1] = ((7 - i) as f32 * r0 + i as f32 * r1) / 7.0;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = ((5 - i) as f32 * r0 + i as f32 * r1) / 5.0;
+        }
+        palette[6] = if signed { -max } else { 0.0 };
+        palette[7] = max;
+    }
+
+    let mut indices = [0_u8; 8];
+    indices[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(indices);
+
+    let mut values = [0.0; 16];
+    for (i, value) in values.iter_mut().enumerate() {
+        *value = palette[((indices >> (3 * i)) & 0x7) as usize] / max;
+    }
+    values
+}
+
+/// Maps a normalised value to 8 bits, SNORM `[-1, 1]` is remapped to `[0, 255]`.
+fn to_unorm8(value: f32, signed: bool) -> u8 {
+    let value = if signed { value * 0.5 + 0.5 } else { value };
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Decodes signed BC4 to a single 8 bit channel, `[-1, 1]` is remapped to `[0, 255]`.
+pub(crate) fn decode_bc4_snorm(src: &[u8], width: usize, height: usize) -> Vec<u8> {
+    let mut output = vec![0; width * height];
+
+    for_each_block(src, width, height, 8, |block, x, y| {
+        for (i, &value) in decode_bc4_channel(block, true).iter().enumerate() {
+            let (px, py) = (x + i % 4, y + i / 4);
+            if px < width && py < height {
+                output[py * width + px] = to_unorm8(value, true);
+            }
+        }
+    });
+
+    output
+}
+
+/// Decodes BC5 to RGBA8 with X in red and Y in green.
+///
+/// Blue is left at zero unless `reconstruct_z` is set, in which case it holds
+/// `Z = sqrt(1 - X² - Y²)` so the result is a viewable tangent-space normal map.
+pub(crate) fn decode_bc5(
+    src: &[u8],
+    width: usize,
+    height: usize,
+    signed: bool,
+    reconstruct_z: bool,
+) -> Vec<u8> {
+    let mut output = vec![0; width * height * 4];
+
+    for_each_block(src, width, height, 16, |block, x, y| {
+        let red = decode_bc4_channel(&block[..8], signed);
+        let green = decode_bc4_channel(&block[8..], signed);
+
+        for (i, (&r, &g)) in red.iter().zip(green.iter()).enumerate() {
+            let (px, py) = (x + i % 4, y + i / 4);
+            if px >= width || py >= height {
+                continue;
+            }
+
+            let pixel = &mut output[(py * width + px) * 4..][..4];
+            pixel[0] = to_unorm8(r, signed);
+            pixel[1] = to_unorm8(g, signed);
+            pixel[3] = 255;
+
+            if reconstruct_z {
+                let (nx, ny) = if signed {
+                    (r, g)
+                } else {
+                    (r * 2.0 - 1.0, g * 2.0 - 1.0)
+                };
+                let nz = (1.0 - nx * nx - ny * ny).max(0.0).sqrt();
+                pixel[2] = to_unorm8(nz, true);
+            }
+        }
+    });
+
+    output
+}
+
+// BC6H endpoint fields, `W`/`X` are the endpoints of the first region and
+// `Y`/`Z` of the second one. `D` is the partition index.
+const RW: u8 = 0;
+const GW: u8 = 1;
+const BW: u8 = 2;
+const RX: u8 = 3;
+const GX: u8 = 4;
+const BX: u8 = 5;
+const RY: u8 = 6;
+const GY: u8 = 7;
+const BY: u8 = 8;
+const RZ: u8 = 9;
+const GZ: u8 = 10;
+const BZ: u8 = 11;
+const D: u8 = 12;
+
+/// A run of `count` bits in the block that holds bits `first..first + count`
+/// of `field`. Reversed runs store the most significant bit first.
+#[derive(Clone, Copy)]
+struct Bc6hBits {
+    field: u8,
+    first: u8,
+    count: u8,
+    reversed: bool,
+}
+
+const fn bits(field: u8, first: u8, count: u8) -> Bc6hBits {
+    Bc6hBits {
+        field,
+        first,
+        count,
+        reversed: false,
+    }
+}
+
+const fn bits_rev(field: u8, first: u8, count: u8) -> Bc6hBits {
+    Bc6hBits {
+        field,
+        first,
+        count,
+        reversed: true,
+    }
+}
+
+struct Bc6hMode {
+    /// Precision of the base endpoint `W`.
+    endpoint_bits: u32,
+    /// Precision of the other endpoints per channel, deltas if `transformed`.
+    delta_bits: [u32; 3],
+    transformed: bool,
+    two_regions: bool,
+    layout: &'static [Bc6hBits],
+}
+
+const BC6H_MODE_1: &[Bc6hBits] = &[
+    bits(GY, 4, 1),
+    bits(BY, 4, 1),
+    bits(BZ, 4, 1),
+    bits(RW, 0, 10),
+    bits(GW, 0, 10),
+    bits(BW, 0, 10),
+    bits(RX, 0, 5),
+    bits(GZ, 4, 1),
+    bits(GY, 0, 4),
+    bits(GX, 0, 5),
+    bits(BZ, 0, 1),
+    bits(GZ, 0, 4),
+    bits(BX, 0, 5),
+    bits(BZ, 1, 1),
+    bits(BY, 0, 4),
+    bits(RY, 0, 5),
+    bits(BZ, 2, 1),
+    bits(RZ, 0, 5),
+    bits(BZ, 3, 1),
+    bits(D, 0, 5),
+];
+
+const BC6H_MODE_2: &[Bc6hBits] = &[
+    bits(GY, 5, 1),
+    bits(GZ, 4, 2),
+    bits(RW, 0, 7),
+    bits(BZ, 0, 2),
+    bits(BY, 4, 1),
+    bits(GW, 0, 7),
+    bits(BY, 5, 1),
+    bits(BZ, 2, 1),
+    bits(GY, 4, 1),
+    bits(BW, 0, 7),
+    bits(BZ, 3, 1),
+    bits(BZ, 5, 1),
+    bits(BZ, 4, 1),
+    bits(RX, 0, 6),
+    bits(GY, 0, 4),
+    bits(GX, 0, 6),
+    bits(GZ, 0, 4),
+    bits(BX, 0, 6),
+    bits(BY, 0, 4),
+    bits(RY, 0, 6),
+    bits(RZ, 0, 6),
+    bits(D, 0, 5),
+];
+
+const BC6H_MODE_3: &[Bc6hBits] = &[
+    bits(RW, 0, 10),
+    bits(GW, 0, 10),
+    bits(BW, 0, 10),
+    bits(RX, 0, 5),
+    bits(RW, 10, 1),
+    bits(GY, 0, 4),
+    bits(GX, 0, 4),
+    bits(GW, 10, 1),
+    bits(BZ, 0, 1),
+    bits(GZ, 0, 4),
+    bits(BX, 0, 4),
+    bits(BW, 10, 1),
+    bits(BZ, 1, 1),
+    bits(BY, 0, 4),
+    bits(RY, 0, 5),
+    bits(BZ, 2, 1),
+    bits(RZ, 0, 5),
+    bits(BZ, 3, 1),
+    bits(D, 0, 5),
+];
+
+const BC6H_MODE_4: &[Bc6hBits] = &[
+    bits(RW, 0, 10),
+    bits(GW, 0, 10),
+    bits(BW, 0, 10),
+    bits(RX, 0, 4),
+    bits(RW, 10, 1),
+    bits(GZ, 4, 1),
+    bits(GY, 0, 4),
+    bits(GX, 0, 5),
+    bits(GW, 10, 1),
+    bits(GZ, 0, 4),
+    bits(BX, 0, 4),
+    bits(BW, 10, 1),
+    bits(BZ, 1, 1),
+    bits(BY, 0, 4),
+    bits(RY, 0, 4),
+    bits(BZ, 0, 1),
+    bits(BZ, 2, 1),
+    bits(RZ, 0, 4),
+    bits(GY, 4, 1),
+    bits(BZ, 3, 1),
+    bits(D, 0, 5),
+];
+
+const BC6H_MODE_5: &[Bc6hBits] = &[
+    bits(RW, 0, 10),
+    bits(GW, 0, 10),
+    bits(BW, 0, 10),
+    bits(RX, 0, 4),
+    bits(RW, 10, 1),
+    bits(BY, 4, 1),
+    bits(GY, 0, 4),
+    bits(GX, 0, 4),
+    bits(GW, 10, 1),
+    bits(BZ, 0, 1),
+    bits(GZ, 0, 4),
+    bits(BX, 0, 5),
+    bits(BW, 10, 1),
+    bits(BY, 0, 4),
+    bits(RY, 0, 4),
+    bits(BZ, 1, 2),
+    bits(RZ, 0, 4),
+    bits(BZ, 4, 1),
+    bits(BZ, 3, 1),
+    bits(D, 0, 5),
+];
+
+const BC6H_MODE_6: &[Bc6hBits] = &[
+    bits(RW, 0, 9),
+    bits(BY, 4, 1),
+    bits(GW, 0, 9),
+    bits(GY, 4, 1),
+    bits(BW, 0, 9),
+    bits(BZ, 4, 1),
+    bits(RX, 0, 5),
+    bits(GZ, 4, 1),
+    bits(GY, 0, 4),
+    bits(GX, 0, 5),
+    bits(BZ, 0, 1),
+    bits(GZ, 0, 4),
+    bits(BX, 0, 5),
+    bits(BZ, 1, 1),
+    bits(BY, 0, 4),
+    bits(RY, 0, 5),
+    bits(BZ, 2, 1),
+    bits(RZ, 0, 5),
+    bits(BZ, 3, 1),
+    bits(D, 0, 5),
+];
+
+const BC6H_MODE_7: &[Bc6hBits] = &[
+    bits(RW, 0, 8),
+    bits(GZ, 4, 1),
+    bits(BY, 4, 1),
+    bits(GW, 0, 8),
+    bits(BZ, 2, 1),
+    bits(GY, 4, 1),
+    bits(BW, 0, 8),
+    bits(BZ, 3, 2),
+    bits(RX, 0, 6),
+    bits(GY, 0, 4),
+    bits(GX, 0, 5),
+    bits(BZ, 0, 1),
+    bits(GZ, 0, 4),
+    bits(BX, 0, 5),
+    bits(BZ, 1, 1),
+    bits(BY, 0, 4),
+    bits(RY, 0, 6),
+    bits(RZ, 0, 6),
+    bits(D, 0, 5),
+];
+
+const BC6H_MODE_8: &[Bc6hBits] = &[
+    bits(RW, 0, 8),
+    bits(BZ, 0, 1),
+    bits(BY, 4, 1),
+    bits(GW, 0, 8),
+    bits(GY, 5, 1),
+    bits(GY, 4, 1),
+    bits(BW, 0, 8),
+    bits(GZ, 5, 1),
+    bits(BZ, 4, 1),
+    bits(RX, 0, 5),
+    bits(GZ, 4, 1),
+    bits(GY, 0, 4),
+    bits(GX, 0, 6),
+    bits(GZ, 0, 4),
+    bits(BX, 0, 5),
+    bits(BZ, 1, 1),
+    bits(BY, 0, 4),
+    bits(RY, 0, 5),
+    bits(BZ, 2, 1),
+    bits(RZ, 0, 5),
+    bits(BZ, 3, 1),
+    bits(D, 0, 5),
+];
+
+const BC6H_MODE_9: &[Bc6hBits] = &[
+    bits(RW, 0, 8),
+    bits(BZ, 1, 1),
+    bits(BY, 4, 1),
+    bits(GW, 0, 8),
+    bits(BY, 5, 1),
+    bits(GY, 4, 1),
+    bits(BW, 0, 8),
+    bits(BZ, 5, 1),
+    bits(BZ, 4, 1),
+    bits(RX, 0, 5),
+    bits(GZ, 4, 1),
+    bits(GY, 0, 4),
+    bits(GX, 0, 5),
+    bits(BZ, 0, 1),
+    bits(GZ, 0, 4),
+    bits(BX, 0, 6),
+    bits(BY, 0, 4),
+    bits(RY, 0, 5),
+    bits(BZ, 2, 1),
+    bits(RZ, 0, 5),
+    bits(BZ, 3, 1),
+    bits(D, 0, 5),
+];
+
+const BC6H_MODE_10: &[Bc6hBits] = &[
+    bits(RW, 0, 6),
+    bits(GZ, 4, 1),
+    bits(BZ, 0, 2),
+    bits(BY, 4, 1),
+    bits(GW, 0, 6),
+    bits(GY, 5, 1),
+    bits(BY, 5, 1),
+    bits(BZ, 2, 1),
+    bits(GY, 4, 1),
+    bits(BW, 0, 6),
+    bits(GZ, 5, 1),
+    bits(BZ, 3, 1),
+    bits(BZ, 5, 1),
+    bits(BZ, 4, 1),
+    bits(RX, 0, 6),
+    bits(GY, 0, 4),
+    bits(GX, 0, 6),
+    bits(GZ, 0, 4),
+    bits(BX, 0, 6),
+    bits(BY, 0, 4),
+    bits(RY, 0, 6),
+    bits(RZ, 0, 6),
+    bits(D, 0, 5),
+];
+
+const BC6H_MODE_11: &[Bc6hBits] = &[
+    bits(RW, 0, 10),
+    bits(GW, 0, 10),
+    bits(BW, 0, 10),
+    bits(RX, 0, 10),
+    bits(GX, 0, 10),
+    bits(BX, 0, 10),
+];
+
+const BC6H_MODE_12: &[Bc6hBits] = &[
+    bits(RW, 0, 10),
+    bits(GW, 0, 10),
+    bits(BW, 0, 10),
+    bits(RX, 0, 9),
+    bits(RW, 10, 1),
+    bits(GX, 0, 9),
+    bits(GW, 10, 1),
+    bits(BX, 0, 9),
+    bits(BW, 10, 1),
+];
+
+const BC6H_MODE_13: &[Bc6hBits] = &[
+    bits(RW, 0, 10),
+    bits(GW, 0, 10),
+    bits(BW, 0, 10),
+    bits(RX, 0, 8),
+    bits_rev(RW, 10, 2),
+    bits(GX, 0, 8),
+    bits_rev(GW, 10, 2),
+    bits(BX, 0, 8),
+    bits_rev(BW, 10, 2),
+];
+
+const BC6H_MODE_14: &[Bc6hBits] = &[
+    bits(RW, 0, 10),
+    bits(GW, 0, 10),
+    bits(BW, 0, 10),
+    bits(RX, 0, 4),
+    bits_rev(RW, 10, 6),
+    bits(GX, 0, 4),
+    bits_rev(GW, 10, 6),
+    bits(BX, 0, 4),
+    bits_rev(BW, 10, 6),
+];
+
+const fn bc6h_mode(
+    endpoint_bits: u32,
+    delta_bits: [u32; 3],
+    transformed: bool,
+    two_regions: bool,
+    layout: &'static [Bc6hBits],
+) -> Bc6hMode {
+    Bc6hMode {
+        endpoint_bits,
+        delta_bits,
+        transformed,
+        two_regions,
+        layout,
+    }
+}
+
+/// Looks up a BC6H mode by its mode bits, `None` for the reserved modes.
+fn bc6h_mode_for(mode_bits: u32) -> Option<Bc6hMode> {
+    Some(match mode_bits {
+        0b00 => bc6h_mode(10, [5, 5, 5], true, true, BC6H_MODE_1),
+        0b01 => bc6h_mode(7, [6, 6, 6], true, true, BC6H_MODE_2),
+        0b00010 => bc6h_mode(11, [5, 4, 4], true, true, BC6H_MODE_3),
+        0b00110 => bc6h_mode(11, [4, 5, 4], true, true, BC6H_MODE_4),
+        0b01010 => bc6h_mode(11, [4, 4, 5], true, true, BC6H_MODE_5),
+        0b01110 => bc6h_mode(9, [5, 5, 5], true, true, BC6H_MODE_6),
+        0b10010 => bc6h_mode(8, [6, 5, 5], true, true, BC6H_MODE_7),
+        0b10110 => bc6h_mode(8, [5, 6, 5], true, true, BC6H_MODE_8),
+        0b11010 => bc6h_mode(8, [5, 5, 6], true, true, BC6H_MODE_9),
+        0b11110 => bc6h_mode(6, [6, 6, 6], false, true, BC6H_MODE_10),
+        0b00011 => bc6h_mode(10, [10, 10, 10], false, false, BC6H_MODE_11),
+        0b00111 => bc6h_mode(11, [9, 9, 9], true, false, BC6H_MODE_12),
+        0b01011 => bc6h_mode(12, [8, 8, 8], true, false, BC6H_MODE_13),
+        0b01111 => bc6h_mode(16, [4, 4, 4], true, false, BC6H_MODE_14),
+        _ => return None,
+    })
+}
+
+/// The first 32 two-subset partitions shared by BC6H and BC7.
+const PARTITIONS_2: [[u8; 16]; 32] = [
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1],
+    [0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1],
+    [0, 1, 1, 1, 0, 1, 1, 1, 0, 1, 1, 1, 0, 1, 1, 1],
+    [0, 0, 0, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 1, 1],
+    [0, 0, 1, 1, 0, 1, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1],
+    [0, 0, 0, 1, 0, 0, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 1, 0, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 1],
+    [0, 0, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 1, 1, 1, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 1, 1],
+    [0, 0, 0, 1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1],
+    [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1],
+    [0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 1, 0, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 1, 0],
+    [0, 1, 1, 1, 0, 0, 1, 1, 0, 0, 0, 1, 0, 0, 0, 0],
+    [0, 0, 1, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 0, 0, 1, 1, 1, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 0, 0],
+    [0, 1, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 0, 1],
+    [0, 0, 1, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0],
+    [0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 1, 0, 0],
+    [0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0],
+    [0, 0, 1, 1, 0, 1, 1, 0, 0, 1, 1, 0, 1, 1, 0, 0],
+    [0, 0, 0, 1, 0, 1, 1, 1, 1, 1, 1, 0, 1, 0, 0, 0],
+    [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0],
+    [0, 1, 1, 1, 0, 0, 0, 1, 1, 0, 0, 0, 1, 1, 1, 0],
+    [0, 0, 1, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 1, 0, 0],
+];
+
+/// Index of the pixel in the second subset whose index drops its top bit.
+const PARTITIONS_2_ANCHOR: [usize; 32] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8,
+    2, 2, 8, 8, 2, 2,
+];
+
+const WEIGHTS_3: [i32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [i32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+struct BitReader {
+    bits: u128,
+    position: u32,
+}
+
+impl BitReader {
+    fn new(block: &[u8]) -> Self {
+        let mut bytes = [0_u8; 16];
+        bytes.copy_from_slice(&block[..16]);
+        BitReader {
+            bits: u128::from_le_bytes(bytes),
+            position: 0,
+        }
+    }
+
+    fn read(&mut self, count: u32) -> u32 {
+        let value = (self.bits >> self.position) as u32 & ((1_u64 << count) - 1) as u32;
+        self.position += count;
+        value
+    }
+}
+
+fn sign_extend(value: i32, bits: u32) -> i32 {
+    let shift = 32 - bits;
+    (value << shift) >> shift
+}
+
+fn unquantize_bc6h(value: i32, bits: u32, signed: bool) -> i32 {
+    if !signed {
+        if bits >= 15 || value == 0 {
+            value
+        } else if value == (1 << bits) - 1 {
+            0xFFFF
+        } else {
+            ((value << 16) + 0x8000) >> bits
+        }
+    } else if bits >= 16 {
+        value
+    } else {
+        let magnitude = value.abs();
+        let unquantized = if magnitude == 0 {
+            0
+        } else if magnitude >= (1 << (bits - 1)) - 1 {
+            0x7FFF
+        } else {
+            ((magnitude << 15) + 0x4000) >> (bits - 1)
+        };
+        if value < 0 {
+            -unquantized
+        } else {
+            unquantized
+        }
+    }
+}
+
+/// Scales an interpolated value back to the bit pattern of a half float.
+fn finish_unquantize_bc6h(value: i32, signed: bool) -> u16 {
+    if !signed {
+        ((value * 31) >> 6) as u16
+    } else if value < 0 {
+        0x8000 | (((-value) * 31) >> 5) as u16
+    } else {
+        ((value * 31) >> 5) as u16
+    }
+}
+
+pub(crate) fn half_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((half >> 10) & 0x1F) as i32;
+    let mantissa = (half & 0x3FF) as f32;
+
+    sign * match exponent {
+        0 => mantissa * 2_f32.powi(-24),
+        31 if mantissa == 0.0 => f32::INFINITY,
+        31 => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2_f32.powi(exponent - 15),
+    }
+}
+
+/// Decodes one BC6H block into 16 RGB half float bit patterns.
+fn decode_bc6h_block(block: &[u8], signed: bool) -> [[u16; 3]; 16] {
+    let mut reader = BitReader::new(block);
+
+    let mut mode_bits = reader.read(2);
+    if mode_bits > 1 {
+        mode_bits |= reader.read(3) << 2;
+    }
+    let mode = match bc6h_mode_for(mode_bits) {
+        Some(mode) => mode,
+        None => return [[0; 3]; 16],
+    };
+
+    let mut fields = [0_i32; 13];
+    for run in mode.layout {
+        let value = reader.read(run.count as u32) as i32;
+        let value = if run.reversed {
+            (value.reverse_bits() as u32 >> (32 - run.count as u32)) as i32
+        } else {
+            value
+        };
+        fields[run.field as usize] |= value << run.first;
+    }
+
+    let endpoint_count = if mode.two_regions { 4 } else { 2 };
+    let mut endpoints = [[0_i32; 3]; 4];
+    for (i, endpoint) in endpoints.iter_mut().take(endpoint_count).enumerate() {
+        endpoint.copy_from_slice(&fields[i * 3..i * 3 + 3]);
+    }
+
+    if signed {
+        for value in endpoints[0].iter_mut() {
+            *value = sign_extend(*value, mode.endpoint_bits);
+        }
+    }
+
+    let base = endpoints[0];
+    let mask = (1 << mode.endpoint_bits) - 1;
+    for endpoint in endpoints.iter_mut().take(endpoint_count).skip(1) {
+        let channels = endpoint
+            .iter_mut()
+            .zip(base.iter().zip(mode.delta_bits.iter()));
+        for (value, (&base, &delta_bits)) in channels {
+            if mode.transformed || signed {
+                *value = sign_extend(*value, delta_bits);
+            }
+            if mode.transformed {
+                *value = (base + *value) & mask;
+                if signed {
+                    *value = sign_extend(*value, mode.endpoint_bits);
+                }
+            }
+        }
+    }
+
+    for endpoint in endpoints.iter_mut().take(endpoint_count) {
+        for value in endpoint.iter_mut() {
+            *value = unquantize_bc6h(*value, mode.endpoint_bits, signed);
+        }
+    }
+
+    let partition = fields[D as usize] as usize;
+    let mut texels = [[0_u16; 3]; 16];
+    for (i, texel) in texels.iter_mut().enumerate() {
+        let (subset, index_bits, weights) = if mode.two_regions {
+            let anchor = i == 0 || i == PARTITIONS_2_ANCHOR[partition];
+            (
+                PARTITIONS_2[partition][i] as usize,
+                if anchor { 2 } else { 3 },
+                &WEIGHTS_3[..],
+            )
+        } else {
+            (0, if i == 0 { 3 } else { 4 }, &WEIGHTS_4[..])
+        };
+
+        let weight = weights[reader.read(index_bits) as usize];
+        let (a, b) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+        for (value, (a, b)) in texel.iter_mut().zip(a.iter().zip(b.iter())) {
+            *value = finish_unquantize_bc6h((a * (64 - weight) + b * weight + 32) >> 6, signed);
+        }
+    }
+
+    texels
+}
+
+/// Decodes BC6H (UF16 or SF16) to linear RGB32F.
+pub(crate) fn decode_bc6h(src: &[u8], width: usize, height: usize, signed: bool) -> Vec<f32> {
+    let mut output = vec![0.0; width * height * 3];
+
+    for_each_block(src, width, height, 16, |block, x, y| {
+        let texels = decode_bc6h_block(block, signed);
+
+        for (i, texel) in texels.iter().enumerate() {
+            let (px, py) = (x + i % 4, y + i / 4);
+            if px >= width || py >= height {
+                continue;
+            }
+
+            let pixel = &mut output[(py * width + px) * 3..][..3];
+            for (value, half) in pixel.iter_mut().zip(texel.iter()) {
+                *value = half_to_f32(*half);
+            }
+        }
+    });
+
+    output
+}
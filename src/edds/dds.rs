@@ -0,0 +1,218 @@
+//! Plain DirectX DDS files.
+//!
+//! A DDS file has the same headers as an EDDS file but no mip table. Its data
+//! is stored surface by surface, each surface holding its mips largest first,
+//! while EDDS stores the mips smallest first with all surfaces of a mip
+//! together (see [`TextureLayout`] for why that order is assumed). Converting
+//! between the two only moves data around, block compressed data is never
+//! decoded.
+
+use std::io::{BufRead, Read, Seek, Write};
+
+use crate::core::errors::EddsError;
+
+use super::{
+    Compression, DdsHeader, DecodeOptions, DecodedFormat, Edds, EncodeOptions, HeaderMode, Mipmap,
+    MipmapType, RawEdds, RawMipmap, StorageFormat, TextureLayout,
+};
+
+/// DDS file with the pixel data still in its stored format.
+#[derive(Debug, Clone)]
+pub struct RawDds {
+    pub header: DdsHeader,
+    /// Mips largest first, every mip holds the data of all its surfaces like
+    /// [`RawMipmap`] does for EDDS.
+    pub mipmaps: Vec<RawMipmap>,
+}
+
+/// Decoded DDS file.
+#[derive(Debug, Clone)]
+pub struct Dds {
+    pub header: DdsHeader,
+    pub format: DecodedFormat,
+    pub layout: TextureLayout,
+    /// Decoded mips, largest mip first.
+    pub mipmaps: Vec<Mipmap>,
+}
+
+impl Dds {
+    pub fn from<I>(input: &mut I) -> Result<Dds, EddsError>
+    where
+        I: Seek + BufRead,
+    {
+        Dds::from_with_options(input, &DecodeOptions::default())
+    }
+
+    pub fn from_with_options<I>(input: &mut I, options: &DecodeOptions) -> Result<Dds, EddsError>
+    where
+        I: Seek + BufRead,
+    {
+        RawDds::from_with_mode(input, options.header_mode)?.decode(options)
+    }
+}
+
+impl RawDds {
+    pub fn from<I>(input: &mut I) -> Result<RawDds, EddsError>
+    where
+        I: Seek + BufRead,
+    {
+        RawDds::from_with_mode(input, HeaderMode::Lenient)
+    }
+
+    /// Like [`RawDds::from`], with [`HeaderMode::Strict`] any header
+    /// diagnostic is an [`EddsError::InvalidHeader`].
+    pub fn from_with_mode<I>(input: &mut I, mode: HeaderMode) -> Result<RawDds, EddsError>
+    where
+        I: Seek + BufRead,
+    {
+        let header = DdsHeader::from_stream(input)?;
+        let diagnostics = header.diagnostics();
+        if mode == HeaderMode::Strict && !diagnostics.is_empty() {
+            return Err(EddsError::InvalidHeader(diagnostics));
+        }
+        let layout = TextureLayout::from_header(&header);
+        let format = StorageFormat::from_header(&header);
+        let mip_count = std::cmp::max(header.mip_map_count, 1);
+
+        // The header sizes aren't trusted, mips are only added while the first
+        // surface is read and their buffers grow with the data actually read.
+        let mut mipmaps: Vec<(usize, RawMipmap)> = Vec::new();
+        for surface in 0..layout.surface_count() {
+            for (index, mip) in (1..=mip_count).enumerate() {
+                if surface == 0 {
+                    let width = Edds::get_dim_for_index(header.width, mip);
+                    let height = Edds::get_dim_for_index(header.height, mip);
+                    let depth = layout.depth_for_index(mip);
+                    let surface_size = format
+                        .data_size(width, height)?
+                        .checked_mul(depth)
+                        .ok_or(EddsError::ImageTooLarge(width, height))?;
+
+                    mipmaps.push((
+                        surface_size,
+                        RawMipmap {
+                            width,
+                            height,
+                            depth,
+                            data_type: MipmapType::COPY,
+                            compressed_data_size: 0,
+                            data: Vec::new(),
+                        },
+                    ));
+                }
+
+                let (surface_size, mipmap) = &mut mipmaps[index];
+                let offset = input.stream_position()?;
+                let read = input
+                    .by_ref()
+                    .take(*surface_size as u64)
+                    .read_to_end(&mut mipmap.data)?;
+                if read != *surface_size {
+                    return Err(EddsError::TruncatedMip {
+                        index,
+                        offset,
+                        expected: *surface_size,
+                        actual: read,
+                    });
+                }
+            }
+        }
+
+        Ok(RawDds {
+            header,
+            mipmaps: mipmaps
+                .into_iter()
+                .map(|(_, mut mipmap)| {
+                    mipmap.compressed_data_size = mipmap.data.len() as u32;
+                    mipmap
+                })
+                .collect(),
+        })
+    }
+
+    /// Encodes a `width` x `height` RGBA8 image, `compression` of the options is ignored.
+    pub fn from_rgba8(
+        width: usize,
+        height: usize,
+        rgba: &[u8],
+        options: &EncodeOptions,
+    ) -> Result<RawDds, EddsError> {
+        Ok(RawDds::from_edds(RawEdds::from_rgba8(
+            width, height, rgba, options,
+        )?))
+    }
+
+    /// Converts EDDS data to DDS, the header is kept as is.
+    pub fn from_edds(edds: RawEdds) -> RawDds {
+        RawDds {
+            header: edds.header,
+            mipmaps: edds
+                .mipmaps
+                .into_iter()
+                .rev()
+                .map(|mipmap| RawMipmap {
+                    data_type: MipmapType::COPY,
+                    compressed_data_size: mipmap.data.len() as u32,
+                    ..mipmap
+                })
+                .collect(),
+        }
+    }
+
+    /// Converts the DDS data to EDDS, marking every mip for `compression` when
+    /// it is written, see [`RawEdds::write`].
+    pub fn into_edds(self, compression: Compression) -> RawEdds {
+        let data_type = match compression {
+            Compression::None => MipmapType::COPY,
+            Compression::Lz4 => MipmapType::LZ4,
+        };
+
+        let mut header = self.header;
+        header.mip_map_count = self.mipmaps.len() as u32;
+
+        RawEdds {
+            header,
+            mipmaps: self
+                .mipmaps
+                .into_iter()
+                .rev()
+                .map(|mipmap| RawMipmap {
+                    data_type,
+                    ..mipmap
+                })
+                .collect(),
+        }
+    }
+
+    /// Decodes every surface of every mip to pixels.
+    pub fn decode(&self, options: &DecodeOptions) -> Result<Dds, EddsError> {
+        let mipmaps = self
+            .mipmaps
+            .iter()
+            .map(|raw| raw.decode(&self.header, options))
+            .collect::<Result<Vec<_>, EddsError>>()?;
+
+        Ok(Dds {
+            header: self.header.clone(),
+            format: DecodedFormat::from_header(&self.header)?,
+            layout: TextureLayout::from_header(&self.header),
+            mipmaps,
+        })
+    }
+
+    /// Writes the headers followed by the data of every surface.
+    pub fn write<W>(&self, output: &mut W) -> Result<(), EddsError>
+    where
+        W: Write,
+    {
+        output.write_all(&self.header.to_stream_bytes()?)?;
+
+        for surface in 0..TextureLayout::from_header(&self.header).surface_count() {
+            for mipmap in &self.mipmaps {
+                output.write_all(mipmap.surface_data(&self.header, surface))?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use std::io::{BufRead, Seek};
+use std::io::{BufRead, Read, Seek};
 
 use deku::DekuEnumExt;
 use deku::{
@@ -6,11 +6,13 @@ use deku::{
     prelude::*,
 };
 use deku::{DekuContainerWrite, DekuRead, DekuUpdate, DekuWrite};
-use enumflags2::{bitflags, BitFlags};
+use enumflags2::{bitflags, BitFlag, BitFlags};
 use four_cc::FourCC;
 
 use crate::core::errors::EddsError;
 
+use super::StorageFormat;
+
 #[derive(Debug, PartialEq, DekuRead, DekuWrite)]
 #[deku(endian = "little", magic = b"DDS ")]
 struct DekuTest {
@@ -59,14 +61,20 @@ pub enum DdsCaps2Flags {
     DDSCAPS2_VOLUME = 0x200000,
 }
 
-#[derive(Debug, PartialEq, Eq, DekuRead, DekuWrite)]
+#[derive(Debug, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
 pub struct DdsPixelFormat {
     pub size: u32,
     #[deku(
-        reader = "DdsPixelFormat::read_flags(deku::rest)",
-        writer = "DdsPixelFormat::write_flags(deku::output, &self.flags)"
+        reader = "peek_known_bits(deku::rest)",
+        writer = "write_bits(deku::output, self.flags.bits() | self.unknown_flags)"
     )]
     pub flags: BitFlags<DdsPixelformatFlags>,
+    /// Bits of `flags` that aren't a known flag, kept so they are written back.
+    #[deku(
+        reader = "read_unknown_bits::<DdsPixelformatFlags>(deku::rest)",
+        writer = "Ok::<(), DekuError>(())"
+    )]
+    pub unknown_flags: u32,
     pub four_cc: FourCCEnum,
     pub rgb_bit_count: u32,
     pub r_bit_mask: u32,
@@ -76,19 +84,44 @@ pub struct DdsPixelFormat {
 }
 
 impl DdsPixelFormat {
-    fn read_flags(
-        rest: &BitSlice<u8, Msb0>,
-    ) -> Result<(&BitSlice<u8, Msb0>, BitFlags<DdsPixelformatFlags>), DekuError> {
-        let (rest, value) = u32::read(rest, ())?;
-        Ok((rest, BitFlags::from_bits(value).unwrap()))
+    pub fn from_four_cc(four_cc: FourCCEnum) -> DdsPixelFormat {
+        DdsPixelFormat {
+            size: 32,
+            flags: DdsPixelformatFlags::DDPF_FOURCC.into(),
+            unknown_flags: 0,
+            four_cc,
+            rgb_bit_count: 0,
+            r_bit_mask: 0,
+            g_bit_mask: 0,
+            b_bit_mask: 0,
+            a_bit_mask: 0,
+        }
     }
 
-    fn write_flags(
-        output: &mut BitVec<u8, Msb0>,
-        flags: &BitFlags<DdsPixelformatFlags>,
-    ) -> Result<(), DekuError> {
-        let value: u32 = flags.bits();
-        value.write(output, ())
+    /// Uncompressed RGB format described by its bit masks, `a_bit_mask` may be 0.
+    pub fn from_masks(
+        rgb_bit_count: u32,
+        r_bit_mask: u32,
+        g_bit_mask: u32,
+        b_bit_mask: u32,
+        a_bit_mask: u32,
+    ) -> DdsPixelFormat {
+        let mut flags = BitFlags::from(DdsPixelformatFlags::DDPF_RGB);
+        if a_bit_mask != 0 {
+            flags |= DdsPixelformatFlags::DDPF_ALPHAPIXELS;
+        }
+
+        DdsPixelFormat {
+            size: 32,
+            flags,
+            unknown_flags: 0,
+            four_cc: FourCCEnum::None,
+            rgb_bit_count,
+            r_bit_mask,
+            g_bit_mask,
+            b_bit_mask,
+            a_bit_mask,
+        }
     }
 }
 
@@ -248,8 +281,55 @@ pub enum DxgiFormat {
     DXGI_FORMAT_FORCE_UINT = 0xffffffff,
 }
 
+impl DxgiFormat {
+    pub fn is_srgb(&self) -> bool {
+        matches!(
+            self,
+            DxgiFormat::DXGI_FORMAT_R8G8B8A8_UNORM_SRGB
+                | DxgiFormat::DXGI_FORMAT_BC1_UNORM_SRGB
+                | DxgiFormat::DXGI_FORMAT_BC2_UNORM_SRGB
+                | DxgiFormat::DXGI_FORMAT_BC3_UNORM_SRGB
+                | DxgiFormat::DXGI_FORMAT_B8G8R8A8_UNORM_SRGB
+                | DxgiFormat::DXGI_FORMAT_B8G8R8X8_UNORM_SRGB
+                | DxgiFormat::DXGI_FORMAT_BC7_UNORM_SRGB
+        )
+    }
+
+    pub fn is_typeless(&self) -> bool {
+        matches!(
+            self,
+            DxgiFormat::DXGI_FORMAT_R32G32B32A32_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_R32G32B32_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_R16G16B16A16_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_R32G32_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_R32G8X24_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_R32_FLOAT_X8X24_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_X32_TYPELESS_G8X24_UINT
+                | DxgiFormat::DXGI_FORMAT_R10G10B10A2_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_R8G8B8A8_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_R16G16_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_R32_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_R24G8_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_R24_UNORM_X8_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_X24_TYPELESS_G8_UINT
+                | DxgiFormat::DXGI_FORMAT_R8G8_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_R16_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_R8_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_BC1_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_BC2_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_BC3_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_BC4_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_BC5_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_B8G8R8A8_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_B8G8R8X8_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_BC6H_TYPELESS
+                | DxgiFormat::DXGI_FORMAT_BC7_TYPELESS
+        )
+    }
+}
+
 /// https://msdn.microsoft.com/en-us/library/bb943991.aspx
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[allow(non_camel_case_types)]
 pub enum DdsPixelFormatEnum {
     D3DFMT_A1R5G5B5,
@@ -274,17 +354,23 @@ pub enum DdsPixelFormatEnum {
     D3DFMT_X8R8G8B8,
 }
 
-#[derive(Debug, PartialEq, Eq, DekuRead, DekuWrite)]
+#[derive(Debug, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
 // #[deku(endian = "little")]
 #[deku(magic = b"DDS ")]
 pub struct DdsHeader {
     #[deku(assert_eq = "124")]
     pub size: u32,
     #[deku(
-        reader = "DdsHeader::read_flags(deku::rest)",
-        writer = "DdsHeader::write_flags(deku::output, &self.flags)"
+        reader = "peek_known_bits(deku::rest)",
+        writer = "write_bits(deku::output, self.flags.bits() | self.unknown_flags)"
     )]
     pub flags: BitFlags<DdsHeaderFlags>,
+    /// Bits of `flags` that aren't a known flag, kept so they are written back.
+    #[deku(
+        reader = "read_unknown_bits::<DdsHeaderFlags>(deku::rest)",
+        writer = "Ok::<(), DekuError>(())"
+    )]
+    pub unknown_flags: u32,
     pub height: u32,
     pub width: u32,
     pub pitch_or_linear_size: u32,
@@ -294,15 +380,27 @@ pub struct DdsHeader {
     pub reserved: Vec<u32>,
     pub ddspf: DdsPixelFormat,
     #[deku(
-        reader = "DdsHeader::read_caps(deku::rest)",
-        writer = "DdsHeader::write_caps(deku::output, &self.caps)"
+        reader = "peek_known_bits(deku::rest)",
+        writer = "write_bits(deku::output, self.caps.bits() | self.unknown_caps)"
     )]
     pub caps: BitFlags<DdsCapsFlags>,
+    /// Bits of `caps` that aren't a known flag.
     #[deku(
-        reader = "DdsHeader::read_caps2(deku::rest)",
-        writer = "DdsHeader::write_caps2(deku::output, &self.caps2)"
+        reader = "read_unknown_bits::<DdsCapsFlags>(deku::rest)",
+        writer = "Ok::<(), DekuError>(())"
+    )]
+    pub unknown_caps: u32,
+    #[deku(
+        reader = "peek_known_bits(deku::rest)",
+        writer = "write_bits(deku::output, self.caps2.bits() | self.unknown_caps2)"
     )]
     pub caps2: BitFlags<DdsCaps2Flags>,
+    /// Bits of `caps2` that aren't a known flag.
+    #[deku(
+        reader = "read_unknown_bits::<DdsCaps2Flags>(deku::rest)",
+        writer = "Ok::<(), DekuError>(())"
+    )]
+    pub unknown_caps2: u32,
     pub caps3: u32,
     pub caps4: u32,
     pub reserved2: u32,
@@ -313,15 +411,80 @@ pub struct DdsHeader {
     pub dx10_header: Option<DdsHeaderDX10>,
 }
 
+/// Marker Enfusion writes into the second reserved header field.
+const ENFUSION_MARKER: u32 = four_cc_to_u32(FourCC(*b"ENF1"));
+
 impl DdsHeader {
+    /// Builds the header of a 2D texture, `dx10_header` must be set when
+    /// `ddspf` uses the `DX10` FourCC.
+    pub fn new(
+        width: u32,
+        height: u32,
+        mip_map_count: u32,
+        ddspf: DdsPixelFormat,
+        dx10_header: Option<DdsHeaderDX10>,
+    ) -> DdsHeader {
+        let mut header = DdsHeader {
+            size: 124,
+            flags: DdsHeaderFlags::DDSD_CAPS
+                | DdsHeaderFlags::DDSD_HEIGHT
+                | DdsHeaderFlags::DDSD_WIDTH
+                | DdsHeaderFlags::DDSD_PIXELFORMAT
+                | DdsHeaderFlags::DDSD_MIPMAPCOUNT,
+            unknown_flags: 0,
+            height,
+            width,
+            pitch_or_linear_size: 0,
+            depth: 0,
+            mip_map_count,
+            reserved: vec![0; 11],
+            ddspf,
+            caps: DdsCapsFlags::DDSCAPS_TEXTURE.into(),
+            unknown_caps: 0,
+            caps2: BitFlags::empty(),
+            unknown_caps2: 0,
+            caps3: 0,
+            caps4: 0,
+            reserved2: 0,
+            dx10_header,
+        };
+        header.reserved[1] = ENFUSION_MARKER;
+
+        if mip_map_count > 1 {
+            header.caps |= DdsCapsFlags::DDSCAPS_COMPLEX | DdsCapsFlags::DDSCAPS_MIPMAP;
+        }
+
+        let format = StorageFormat::from_header(&header);
+        if let Some((block_width, block_size)) = format.block_layout() {
+            if format.is_block_compressed() {
+                header.flags |= DdsHeaderFlags::DDSD_LINEARSIZE;
+                header.pitch_or_linear_size = format
+                    .data_size(width as usize, height as usize)
+                    .unwrap_or(0) as u32;
+            } else {
+                header.flags |= DdsHeaderFlags::DDSD_PITCH;
+                header.pitch_or_linear_size = (width as usize / block_width * block_size) as u32;
+            }
+        }
+
+        header
+    }
+
+    /// Serializes the header including the DX10 header, if any.
+    pub fn to_stream_bytes(&self) -> Result<Vec<u8>, EddsError> {
+        let mut bytes = self.to_bytes()?;
+        if let Some(dx10_header) = &self.dx10_header {
+            bytes.extend(dx10_header.to_bytes()?);
+        }
+        Ok(bytes)
+    }
+
     pub fn from_stream<R>(reader: &mut R) -> Result<Self, EddsError>
     where
         R: Seek + BufRead,
     {
-        let mut buf = [0; 128];
-        let read = reader.read(&mut buf)?;
-        assert_eq!(read, 128);
-        let (_, mut header) = DdsHeader::from_bytes((&buf, 0))?;
+        let buf = read_header_bytes(reader, 0, 128)?;
+        let (_, mut header) = DdsHeader::from_bytes((buf.as_slice(), 0))?;
 
         if header
             .ddspf
@@ -329,10 +492,8 @@ impl DdsHeader {
             .contains(DdsPixelformatFlags::DDPF_FOURCC)
             && header.ddspf.four_cc == FourCCEnum::DX10
         {
-            let mut buf = [0; 20];
-            let read = reader.read(&mut buf)?;
-            assert_eq!(read, 20);
-            let (_, dx10_header) = DdsHeaderDX10::from_bytes((&buf, 0))?;
+            let buf = read_header_bytes(reader, 128, 20)?;
+            let (_, dx10_header) = DdsHeaderDX10::from_bytes((buf.as_slice(), 0))?;
 
             header.dx10_header = Some(dx10_header);
         }
@@ -370,56 +531,10 @@ impl DdsHeader {
             (_, _, _, _, _) => DdsPixelFormatEnum::Unknown,
         }
     }
-
-    fn read_flags(
-        rest: &BitSlice<u8, Msb0>,
-    ) -> Result<(&BitSlice<u8, Msb0>, BitFlags<DdsHeaderFlags>), DekuError> {
-        let (rest, value) = u32::read(rest, ())?;
-        Ok((rest, BitFlags::from_bits(value).unwrap()))
-    }
-
-    /// Parse from String to u8 and write
-    fn write_flags(
-        output: &mut BitVec<u8, Msb0>,
-        flags: &BitFlags<DdsHeaderFlags>,
-    ) -> Result<(), DekuError> {
-        let value: u32 = flags.bits();
-        value.write(output, ())
-    }
-
-    fn read_caps(
-        rest: &BitSlice<u8, Msb0>,
-    ) -> Result<(&BitSlice<u8, Msb0>, BitFlags<DdsCapsFlags>), DekuError> {
-        let (rest, value) = u32::read(rest, ())?;
-        Ok((rest, BitFlags::from_bits(value).unwrap()))
-    }
-
-    fn write_caps(
-        output: &mut BitVec<u8, Msb0>,
-        flags: &BitFlags<DdsCapsFlags>,
-    ) -> Result<(), DekuError> {
-        let value: u32 = flags.bits();
-        value.write(output, ())
-    }
-
-    fn read_caps2(
-        rest: &BitSlice<u8, Msb0>,
-    ) -> Result<(&BitSlice<u8, Msb0>, BitFlags<DdsCaps2Flags>), DekuError> {
-        let (rest, value) = u32::read(rest, ())?;
-        Ok((rest, BitFlags::from_bits(value).unwrap()))
-    }
-
-    fn write_caps2(
-        output: &mut BitVec<u8, Msb0>,
-        flags: &BitFlags<DdsCaps2Flags>,
-    ) -> Result<(), DekuError> {
-        let value: u32 = flags.bits();
-        value.write(output, ())
-    }
 }
 
 #[repr(u32)]
-#[derive(Debug, PartialEq, Eq, DekuRead, DekuWrite)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, DekuRead, DekuWrite)]
 #[deku(type = "u32", bytes = "4")]
 pub enum FourCCEnum {
     None = 0,
@@ -429,8 +544,8 @@ pub enum FourCCEnum {
     DXT4 = four_cc_to_u32(FourCC(*b"DXT4")),
     DXT5 = four_cc_to_u32(FourCC(*b"DXT5")),
     DX10 = four_cc_to_u32(FourCC(*b"DX10")),
-    ATT1 = four_cc_to_u32(FourCC(*b"ATT1")),
-    ATT2 = four_cc_to_u32(FourCC(*b"ATT2")),
+    ATI1 = four_cc_to_u32(FourCC(*b"ATI1")),
+    ATI2 = four_cc_to_u32(FourCC(*b"ATI2")),
     BC4U = four_cc_to_u32(FourCC(*b"BC4U")),
     BC4S = four_cc_to_u32(FourCC(*b"BC4S")),
     BC5U = four_cc_to_u32(FourCC(*b"BC5U")),
@@ -446,7 +561,7 @@ const fn four_cc_to_u32(four_cc: FourCC) -> u32 {
         + ((four_cc.0[3] as u32) << 24)
 }
 
-#[derive(Debug, PartialEq, Eq, DekuRead, DekuWrite)]
+#[derive(Debug, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
 pub struct DdsHeaderDX10 {
     pub dxgi_format: DxgiFormat,
     pub resource_dimension: D3D10_Resource_Dimension,
@@ -455,6 +570,18 @@ pub struct DdsHeaderDX10 {
     pub misc_flags2: u32,
 }
 
+impl DdsHeaderDX10 {
+    pub fn new_texture_2d(dxgi_format: DxgiFormat) -> DdsHeaderDX10 {
+        DdsHeaderDX10 {
+            dxgi_format,
+            resource_dimension: D3D10_Resource_Dimension::D3D10_RESOURCE_DIMENSION_TEXTURE2D,
+            misc_flag: 0,
+            array_size: 1,
+            misc_flags2: 0,
+        }
+    }
+}
+
 impl Default for DdsHeaderDX10 {
     fn default() -> Self {
         Self {
@@ -466,3 +593,45 @@ impl Default for DdsHeaderDX10 {
         }
     }
 }
+
+/// Reads `size` bytes of a header that starts at `offset` in the file.
+fn read_header_bytes<R>(reader: &mut R, offset: u64, size: usize) -> Result<Vec<u8>, EddsError>
+where
+    R: Read,
+{
+    let mut buf = Vec::with_capacity(size);
+    reader.by_ref().take(size as u64).read_to_end(&mut buf)?;
+    if buf.len() != size {
+        return Err(EddsError::TruncatedHeader {
+            offset,
+            expected: size,
+            actual: buf.len(),
+        });
+    }
+    Ok(buf)
+}
+
+/// Reads the known bits of a flag field without consuming it, the field is
+/// consumed by [`read_unknown_bits`] on the field that follows.
+fn peek_known_bits<T>(
+    rest: &BitSlice<u8, Msb0>,
+) -> Result<(&BitSlice<u8, Msb0>, BitFlags<T>), DekuError>
+where
+    T: BitFlag<Numeric = u32>,
+{
+    let (_, value) = u32::read(rest, ())?;
+    Ok((rest, BitFlags::from_bits_truncate(value)))
+}
+
+/// Reads a flag field and keeps the bits that aren't a flag of `T`.
+fn read_unknown_bits<T>(rest: &BitSlice<u8, Msb0>) -> Result<(&BitSlice<u8, Msb0>, u32), DekuError>
+where
+    T: BitFlag<Numeric = u32>,
+{
+    let (rest, value) = u32::read(rest, ())?;
+    Ok((rest, value & !BitFlags::<T>::all().bits()))
+}
+
+fn write_bits(output: &mut BitVec<u8, Msb0>, value: u32) -> Result<(), DekuError> {
+    value.write(output, ())
+}
@@ -1,122 +1,260 @@
-use std::io::{BufRead, Seek};
+use std::io::{BufRead, Read, Seek};
 
-use crate::core::{errors::EddsError, read::ReadExtTrait};
+use crate::core::errors::EddsError;
 
 use super::{
+    bcn,
     dds_header::{DdsHeader, DxgiFormat},
-    DdsPixelFormatEnum, FourCCEnum,
+    format::{pixel_format_layout, DecodedFormat},
+    lz4_stream::decompress_lz4_mip,
+    raw::RawEdds,
+    uncompressed, DdsPixelFormatEnum, EddsInfo, FourCCEnum, HeaderMode, StorageFormat,
+    TextureLayout,
 };
 
-use lzzzz::lz4;
-
 #[derive(Debug, Clone)]
 pub struct Edds {
+    pub header: DdsHeader,
+    /// Storage of each mip as listed in the file, smallest mip first.
+    pub blocks: Vec<MipmapBlock>,
+    pub format: DecodedFormat,
+    pub layout: TextureLayout,
+    /// Decoded mips, smallest mip first like `blocks`.
     pub mipmaps: Vec<Mipmap>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MipmapType {
     COPY,
     LZ4,
 }
 
+/// Entry of the mip table that follows the DDS header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MipmapBlock {
+    pub data_type: MipmapType,
+    /// Size of the stored mip data, including the LZ4 framing.
+    pub size: u32,
+}
+
 #[derive(Debug, Clone)]
 pub struct Mipmap {
     pub width: usize,
     pub height: usize,
     pub data_type: MipmapType,
     pub compressed_data_size: u32,
-    pub data: Vec<u8>,
+    /// Number of depth slices in every surface, 1 unless the texture is a volume.
+    pub depth: usize,
+    pub format: DecodedFormat,
+    /// Pixels of every cube face or array layer, in the order of [`TextureLayout`].
+    /// That order is provisional for textures with more than one surface.
+    pub surfaces: Vec<PixelData>,
 }
 
-impl Edds {
-    pub fn from<I>(input: &mut I) -> Result<Edds, EddsError>
-    where
-        I: Seek + BufRead,
-    {
-        let header = DdsHeader::from_stream(input)?;
-        let mut mipmaps = Vec::new();
-
-        for i in (1..(header.mip_map_count + 1)).rev() {
-            let data_type = input.read_string_lossy(4)?;
-            let compressed_data_size = input.read_u32()?;
-            mipmaps.push(Mipmap {
-                width: Edds::get_dim_for_index(header.width, i),
-                height: Edds::get_dim_for_index(header.height, i),
-                data_type: match data_type.as_str() {
-                    "COPY" => MipmapType::COPY,
-                    "LZ4 " => MipmapType::LZ4,
-                    unk => return Err(EddsError::UnknownImageDataType(format!("{:?}", unk))),
-                },
-                data: Vec::new(),
-                compressed_data_size,
-            });
+impl Mipmap {
+    /// Pixels of a texture with a single surface, cubemaps and arrays fail with
+    /// [`EddsError::MultipleSurfaces`] and are read through `surfaces`.
+    pub fn data(&self) -> Result<&PixelData, EddsError> {
+        match self.surfaces.as_slice() {
+            [data] => Ok(data),
+            surfaces => Err(EddsError::MultipleSurfaces(surfaces.len())),
         }
+    }
+}
 
-        for mipmap in mipmaps.iter_mut() {
-            match mipmap.data_type {
-                MipmapType::COPY => {
-                    let mut buf = vec![0; mipmap.compressed_data_size as usize];
-                    input.read_exact(&mut buf).unwrap();
-                    mipmap.data = Edds::decode_data(&buf, mipmap.width, mipmap.height, &header)?;
-                }
-                MipmapType::LZ4 => {
-                    let mut lz4_stream = lz4::Decompressor::new().unwrap();
+/// Decoded pixels of a mipmap, laid out as described by [`DecodedFormat`].
+///
+/// Most formats decode to 8 bits per channel, 16 bit and 10 bit UNORM formats
+/// decode to `u16` and HDR formats like BC6H decode to linear `f32`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PixelData {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    F32(Vec<f32>),
+}
 
-                    let uncompressed_data_size = input.read_u32().unwrap() as usize;
+impl PixelData {
+    pub fn as_u8(&self) -> Option<&[u8]> {
+        match self {
+            PixelData::U8(data) => Some(data),
+            _ => None,
+        }
+    }
 
-                    let mut data_read = 4;
-                    let mut complete_buffer = Vec::with_capacity(uncompressed_data_size as usize);
+    pub fn as_u16(&self) -> Option<&[u16]> {
+        match self {
+            PixelData::U16(data) => Some(data),
+            _ => None,
+        }
+    }
 
-                    loop {
-                        let compress_block_size = input.read_u24().unwrap() as usize;
-                        data_read += 3;
+    pub fn as_f32(&self) -> Option<&[f32]> {
+        match self {
+            PixelData::F32(data) => Some(data),
+            _ => None,
+        }
+    }
+}
 
-                        let is_last_block = input.read_u8().unwrap() as u32 != 0;
-                        data_read += 1;
+/// Options for decoding, start from [`DecodeOptions::default`] and set the
+/// fields that differ.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct DecodeOptions {
+    /// Rebuild the blue channel of two channel normal maps (BC5) from X and Y.
+    pub reconstruct_normal_z: bool,
+    pub header_mode: HeaderMode,
+}
 
-                        let mut buf = vec![0; compress_block_size];
-                        input.read_exact(&mut buf).unwrap();
+impl Edds {
+    pub fn from<I>(input: &mut I) -> Result<Edds, EddsError>
+    where
+        I: Seek + BufRead,
+    {
+        Edds::from_with_options(input, &DecodeOptions::default())
+    }
+
+    pub fn from_with_options<I>(input: &mut I, options: &DecodeOptions) -> Result<Edds, EddsError>
+    where
+        I: Seek + BufRead,
+    {
+        RawEdds::from_with_mode(input, options.header_mode)?.decode(options)
+    }
 
-                        data_read += compress_block_size;
+    /// Total size of the stored mip data, excluding the headers and the mip table.
+    pub fn stored_size(&self) -> u64 {
+        self.blocks.iter().map(|block| block.size as u64).sum()
+    }
 
-                        let mut block_size = 65536;
-                        if is_last_block {
-                            block_size = uncompressed_data_size - complete_buffer.len();
-                        }
+    /// Reads the data of the mip at `index` in `info` from the current position
+    /// of `input`, decompressing LZ4 mips.
+    pub(crate) fn read_block<I>(
+        input: &mut I,
+        info: &EddsInfo,
+        index: usize,
+    ) -> Result<Vec<u8>, EddsError>
+    where
+        I: Seek + BufRead,
+    {
+        let mipmap = &info.mipmaps[index];
+        let expected = mipmap.block.size as usize;
 
-                        let decomp = lz4_stream.next(&buf, block_size as usize).unwrap();
-                        complete_buffer.append(&mut decomp.to_owned());
+        let mut buf = Vec::with_capacity(expected);
+        input.by_ref().take(expected as u64).read_to_end(&mut buf)?;
+        if buf.len() != expected {
+            return Err(EddsError::TruncatedMip {
+                index,
+                offset: mipmap.offset,
+                expected,
+                actual: buf.len(),
+            });
+        }
 
-                        if is_last_block {
-                            assert_eq!(data_read, mipmap.compressed_data_size as usize);
-                            break;
-                        }
-                    }
+        let data = match mipmap.block.data_type {
+            MipmapType::COPY => buf,
+            MipmapType::LZ4 => {
+                decompress_lz4_mip(&buf).map_err(|error| EddsError::MipDecompression {
+                    index,
+                    offset: mipmap.offset,
+                    source: Box::new(error),
+                })?
+            }
+        };
 
-                    mipmap.data =
-                        Edds::decode_data(&complete_buffer, mipmap.width, mipmap.height, &header)?;
-                }
-            };
+        if let Some(expected) = info.mip_data_size(index) {
+            if data.len() < expected {
+                return Err(EddsError::MipSizeMismatch {
+                    index,
+                    offset: mipmap.offset,
+                    expected,
+                    actual: data.len(),
+                });
+            }
         }
 
-        Ok(Edds { mipmaps })
+        Ok(data)
     }
 
-    fn get_dim_for_index(max_dim: u32, index: u32) -> usize {
-        std::cmp::max(max_dim / 2_u32.pow(index - 1), 1) as usize
+    pub(crate) fn get_dim_for_index(max_dim: u32, index: u32) -> usize {
+        std::cmp::max(max_dim.checked_shr(index - 1).unwrap_or(0), 1) as usize
     }
 
-    fn decode_data(
+    /// Decodes a single `width` x `height` surface.
+    ///
+    /// Block compressed mips are stored as whole 4x4 blocks, so a 1x1 or 3x5
+    /// mip still takes a full block per started 4 pixels in each direction.
+    /// They are decoded at the padded size and cropped, which keeps the result
+    /// independent of how the block decoders treat partial blocks. Bytes past
+    /// the size of the surface are ignored.
+    pub(crate) fn decode_data(
         src: &[u8],
         width: usize,
         height: usize,
         header: &DdsHeader,
-    ) -> Result<Vec<u8>, EddsError> {
-        match &header.dx10_header {
-            Some(dx10_header) => decode_dx10_data(dx10_header, src, width, height),
-            None => decode_four_cc_data(header, src, width, height),
+        options: &DecodeOptions,
+    ) -> Result<PixelData, EddsError> {
+        let storage_format = StorageFormat::from_header(header);
+        let src = match storage_format.block_layout() {
+            Some(_) => {
+                let size = storage_format.data_size(width, height)?;
+                if src.len() < size {
+                    return Err(EddsError::MipDataTooShort(size, src.len()));
+                }
+                &src[..size]
+            }
+            None => src,
+        };
+
+        let (decode_width, decode_height) = match storage_format.block_layout() {
+            Some((block_width, _)) => (
+                width.div_ceil(block_width) * block_width,
+                height.div_ceil(block_width) * block_width,
+            ),
+            None => (width, height),
+        };
+
+        let data = match &header.dx10_header {
+            Some(dx10_header) => {
+                decode_dx10_data(dx10_header, src, decode_width, decode_height, options)?
+            }
+            None => decode_four_cc_data(header, src, decode_width, decode_height, options)?,
+        };
+
+        if (decode_width, decode_height) == (width, height) {
+            return Ok(data);
         }
+
+        let channels = DecodedFormat::from_header(header)?.channels as usize;
+        Ok(crop_pixel_data(data, decode_width, width, height, channels))
+    }
+}
+
+/// Keeps the top left `width` x `height` pixels of an image `stride` pixels wide.
+fn crop_pixel_data(
+    data: PixelData,
+    stride: usize,
+    width: usize,
+    height: usize,
+    channels: usize,
+) -> PixelData {
+    fn crop<T: Copy>(
+        data: &[T],
+        stride: usize,
+        width: usize,
+        height: usize,
+        channels: usize,
+    ) -> Vec<T> {
+        data.chunks_exact(stride * channels)
+            .take(height)
+            .flat_map(|row| &row[..width * channels])
+            .copied()
+            .collect()
+    }
+
+    match data {
+        PixelData::U8(data) => PixelData::U8(crop(&data, stride, width, height, channels)),
+        PixelData::U16(data) => PixelData::U16(crop(&data, stride, width, height, channels)),
+        PixelData::F32(data) => PixelData::F32(crop(&data, stride, width, height, channels)),
     }
 }
 
@@ -125,36 +263,99 @@ fn decode_four_cc_data(
     src: &[u8],
     width: usize,
     height: usize,
-) -> Result<Vec<u8>, EddsError> {
-    match &header.ddspf.four_cc {
-        FourCCEnum::None => decode_pixel_format_data(header, src),
-        FourCCEnum::DXT5 => {
-            let bc5 = texpresso::Format::Bc5;
-            let mut output = vec![0; width * height * 4];
-            bc5.decompress(src, width, height, &mut output);
-            Ok(output)
-        }
-        ni_four_cc => Err(EddsError::UnknownImageDataFormat(format!(
-            "{:?}",
-            ni_four_cc
-        ))),
-    }
+    options: &DecodeOptions,
+) -> Result<PixelData, EddsError> {
+    let data = match &header.ddspf.four_cc {
+        FourCCEnum::None => return decode_pixel_format_data(header, src),
+        FourCCEnum::DXT1 => decode_texpresso(texpresso::Format::Bc1, src, width, height),
+        FourCCEnum::DXT2 => {
+            let mut output = decode_texpresso(texpresso::Format::Bc2, src, width, height);
+            unpremultiply_alpha(&mut output);
+            output
+        }
+        FourCCEnum::DXT3 => decode_texpresso(texpresso::Format::Bc2, src, width, height),
+        FourCCEnum::DXT4 => {
+            let mut output = decode_texpresso(texpresso::Format::Bc3, src, width, height);
+            unpremultiply_alpha(&mut output);
+            output
+        }
+        FourCCEnum::DXT5 => decode_texpresso(texpresso::Format::Bc3, src, width, height),
+        FourCCEnum::ATI1 | FourCCEnum::BC4U => decode_bc4_unorm(src, width, height)?,
+        FourCCEnum::BC4S => bcn::decode_bc4_snorm(src, width, height),
+        FourCCEnum::ATI2 | FourCCEnum::BC5U => {
+            bcn::decode_bc5(src, width, height, false, options.reconstruct_normal_z)
+        }
+        FourCCEnum::BC5S => bcn::decode_bc5(src, width, height, true, options.reconstruct_normal_z),
+        ni_four_cc => {
+            return Err(EddsError::UnknownImageDataFormat(format!(
+                "{:?}",
+                ni_four_cc
+            )))
+        }
+    };
+
+    Ok(PixelData::U8(data))
 }
 
-fn decode_pixel_format_data(header: &DdsHeader, src: &[u8]) -> Result<Vec<u8>, EddsError> {
-    match header.get_pixel_format() {
-        DdsPixelFormatEnum::D3DFMT_X8R8G8B8 | DdsPixelFormatEnum::D3DFMT_A8R8G8B8 => {
-            let mut src = src.to_vec();
-            for i in (0..src.len()).step_by(4) {
-                let r = src[i];
-                let b = src[i + 2];
+/// Decodes BC1 (DXT1), BC2 (DXT2/DXT3) and BC3 (DXT4/DXT5) blocks to RGBA8.
+///
+/// BC1 blocks with `color0 <= color1` use the 3-colour mode, where index 3 is
+/// transparent black (1-bit punch-through alpha).
+fn decode_texpresso(format: texpresso::Format, src: &[u8], width: usize, height: usize) -> Vec<u8> {
+    let mut output = vec![0; width * height * 4];
+    format.decompress(src, width, height, &mut output);
+    output
+}
 
-                src[i] = b;
-                src[i + 2] = r;
-            }
-            Ok(src)
+fn decode_bc4_unorm(src: &[u8], width: usize, height: usize) -> Result<Vec<u8>, EddsError> {
+    Ok(bcndecode::decode(
+        src,
+        width,
+        height,
+        bcndecode::BcnEncoding::Bc4,
+        bcndecode::BcnDecoderFormat::LUM,
+    )?)
+}
+
+/// DXT2 and DXT4 store colour premultiplied by alpha, divide it back out so
+/// the output matches the straight alpha of every other format.
+fn unpremultiply_alpha(rgba: &mut [u8]) {
+    for pixel in rgba.chunks_exact_mut(4) {
+        let a = pixel[3] as u32;
+        if a == 0 || a == 255 {
+            continue;
+        }
+        for c in pixel[..3].iter_mut() {
+            *c = std::cmp::min((*c as u32 * 255 + a / 2) / a, 255) as u8;
         }
-        unk => Err(EddsError::UnknownImageDataFormat(format!("{:?}", unk))),
+    }
+}
+
+/// Decodes uncompressed legacy formats from the bit masks of the pixel format.
+///
+/// Luminance formats with alpha (A8L8, A4L4) are expanded to RGBA, formats with
+/// channels wider than 8 bits (A2R10G10B10, G16R16) decode to 16 bits.
+fn decode_pixel_format_data(header: &DdsHeader, src: &[u8]) -> Result<PixelData, EddsError> {
+    let format = header.get_pixel_format();
+    match format {
+        DdsPixelFormatEnum::D3DFMT_L8 => return Ok(PixelData::U8(src.to_vec())),
+        DdsPixelFormatEnum::D3DFMT_L16 => return Ok(PixelData::U16(uncompressed::decode_u16(src))),
+        _ => {}
+    }
+
+    let (bytes_per_pixel, masks) = pixel_format_layout(&header.ddspf, &format)?;
+    if masks.iter().any(|mask| mask.count_ones() > 8) {
+        Ok(PixelData::U16(uncompressed::decode_masked_u16(
+            src,
+            bytes_per_pixel,
+            masks,
+        )))
+    } else {
+        Ok(PixelData::U8(uncompressed::decode_masked_u8(
+            src,
+            bytes_per_pixel,
+            masks,
+        )))
     }
 }
 
@@ -163,34 +364,133 @@ fn decode_dx10_data(
     src: &[u8],
     width: usize,
     height: usize,
-) -> Result<Vec<u8>, EddsError> {
-    match dx10_header.dxgi_format {
-        DxgiFormat::DXGI_FORMAT_BC4_UNORM => Ok(bcndecode::decode(
+    options: &DecodeOptions,
+) -> Result<PixelData, EddsError> {
+    Ok(match dx10_header.dxgi_format {
+        DxgiFormat::DXGI_FORMAT_BC1_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_BC1_UNORM
+        | DxgiFormat::DXGI_FORMAT_BC1_UNORM_SRGB => {
+            PixelData::U8(decode_texpresso(texpresso::Format::Bc1, src, width, height))
+        }
+        DxgiFormat::DXGI_FORMAT_BC2_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_BC2_UNORM
+        | DxgiFormat::DXGI_FORMAT_BC2_UNORM_SRGB => {
+            PixelData::U8(decode_texpresso(texpresso::Format::Bc2, src, width, height))
+        }
+        DxgiFormat::DXGI_FORMAT_BC3_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_BC3_UNORM
+        | DxgiFormat::DXGI_FORMAT_BC3_UNORM_SRGB => {
+            PixelData::U8(decode_texpresso(texpresso::Format::Bc3, src, width, height))
+        }
+        DxgiFormat::DXGI_FORMAT_BC4_TYPELESS | DxgiFormat::DXGI_FORMAT_BC4_UNORM => {
+            PixelData::U8(decode_bc4_unorm(src, width, height)?)
+        }
+        DxgiFormat::DXGI_FORMAT_BC4_SNORM => {
+            PixelData::U8(bcn::decode_bc4_snorm(src, width, height))
+        }
+        DxgiFormat::DXGI_FORMAT_BC5_TYPELESS | DxgiFormat::DXGI_FORMAT_BC5_UNORM => PixelData::U8(
+            bcn::decode_bc5(src, width, height, false, options.reconstruct_normal_z),
+        ),
+        DxgiFormat::DXGI_FORMAT_BC5_SNORM => PixelData::U8(bcn::decode_bc5(
             src,
             width,
             height,
-            bcndecode::BcnEncoding::Bc4,
-            bcndecode::BcnDecoderFormat::LUM,
-        )?),
-        DxgiFormat::DXGI_FORMAT_B8G8R8X8_UNORM_SRGB => {
-            let mut src = src.to_vec();
-            for i in (0..src.len()).step_by(4) {
-                let r = src[i];
-                let b = src[i + 2];
-
-                src[i] = b;
-                src[i + 2] = r;
-            }
-            Ok(src)
+            true,
+            options.reconstruct_normal_z,
+        )),
+        DxgiFormat::DXGI_FORMAT_BC6H_TYPELESS | DxgiFormat::DXGI_FORMAT_BC6H_UF16 => {
+            PixelData::F32(bcn::decode_bc6h(src, width, height, false))
         }
-        DxgiFormat::DXGI_FORMAT_BC7_UNORM_SRGB => {
+        DxgiFormat::DXGI_FORMAT_BC6H_SF16 => {
+            PixelData::F32(bcn::decode_bc6h(src, width, height, true))
+        }
+        DxgiFormat::DXGI_FORMAT_BC7_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_BC7_UNORM
+        | DxgiFormat::DXGI_FORMAT_BC7_UNORM_SRGB => {
             let mut dst = vec![0_u8; width * height * 4];
             bcdec_rust::bcdec_bc7_unorm_safer(src, width, height, &mut dst);
-            Ok(dst)
+            PixelData::U8(dst)
         }
-        _ => Err(EddsError::UnknownImageDataFormat(format!(
-            "{:?}",
-            dx10_header.dxgi_format
-        ))),
-    }
+        DxgiFormat::DXGI_FORMAT_R8G8B8A8_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_R8G8B8A8_UNORM
+        | DxgiFormat::DXGI_FORMAT_R8G8B8A8_UNORM_SRGB
+        | DxgiFormat::DXGI_FORMAT_R8_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_R8_UNORM => PixelData::U8(src.to_vec()),
+        DxgiFormat::DXGI_FORMAT_R8G8B8A8_SNORM | DxgiFormat::DXGI_FORMAT_R8_SNORM => {
+            PixelData::U8(uncompressed::snorm8_to_unorm8(src))
+        }
+        DxgiFormat::DXGI_FORMAT_B8G8R8A8_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_B8G8R8A8_UNORM
+        | DxgiFormat::DXGI_FORMAT_B8G8R8A8_UNORM_SRGB => PixelData::U8(
+            uncompressed::decode_masked_u8(src, 4, [0xFF0000, 0xFF00, 0xFF, 0xFF000000]),
+        ),
+        DxgiFormat::DXGI_FORMAT_B8G8R8X8_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_B8G8R8X8_UNORM
+        | DxgiFormat::DXGI_FORMAT_B8G8R8X8_UNORM_SRGB => PixelData::U8(
+            uncompressed::decode_masked_u8(src, 4, [0xFF0000, 0xFF00, 0xFF, 0]),
+        ),
+        DxgiFormat::DXGI_FORMAT_R8G8_TYPELESS | DxgiFormat::DXGI_FORMAT_R8G8_UNORM => {
+            PixelData::U8(uncompressed::decode_masked_u8(src, 2, [0xFF, 0xFF00, 0, 0]))
+        }
+        DxgiFormat::DXGI_FORMAT_R8G8_SNORM => PixelData::U8(uncompressed::decode_masked_u8(
+            &uncompressed::snorm8_to_unorm8(src),
+            2,
+            [0xFF, 0xFF00, 0, 0],
+        )),
+        DxgiFormat::DXGI_FORMAT_A8_UNORM => {
+            PixelData::U8(uncompressed::decode_masked_u8(src, 1, [0, 0, 0, 0xFF]))
+        }
+        DxgiFormat::DXGI_FORMAT_B5G6R5_UNORM => PixelData::U8(uncompressed::decode_masked_u8(
+            src,
+            2,
+            [0xF800, 0x7E0, 0x1F, 0],
+        )),
+        DxgiFormat::DXGI_FORMAT_B5G5R5A1_UNORM => PixelData::U8(uncompressed::decode_masked_u8(
+            src,
+            2,
+            [0x7C00, 0x3E0, 0x1F, 0x8000],
+        )),
+        DxgiFormat::DXGI_FORMAT_B4G4R4A4_UNORM => PixelData::U8(uncompressed::decode_masked_u8(
+            src,
+            2,
+            [0xF00, 0xF0, 0xF, 0xF000],
+        )),
+        DxgiFormat::DXGI_FORMAT_R16_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_R16_UNORM
+        | DxgiFormat::DXGI_FORMAT_R16G16B16A16_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_R16G16B16A16_UNORM => {
+            PixelData::U16(uncompressed::decode_u16(src))
+        }
+        DxgiFormat::DXGI_FORMAT_R16G16_TYPELESS | DxgiFormat::DXGI_FORMAT_R16G16_UNORM => {
+            PixelData::U16(uncompressed::decode_masked_u16(
+                src,
+                4,
+                [0xFFFF, 0xFFFF0000, 0, 0],
+            ))
+        }
+        DxgiFormat::DXGI_FORMAT_R10G10B10A2_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_R10G10B10A2_UNORM => PixelData::U16(
+            uncompressed::decode_masked_u16(src, 4, [0x3FF, 0xFFC00, 0x3FF00000, 0xC0000000]),
+        ),
+        DxgiFormat::DXGI_FORMAT_R16_FLOAT | DxgiFormat::DXGI_FORMAT_R16G16B16A16_FLOAT => {
+            PixelData::F32(uncompressed::decode_f16(src))
+        }
+        DxgiFormat::DXGI_FORMAT_R32_FLOAT
+        | DxgiFormat::DXGI_FORMAT_R32G32B32_FLOAT
+        | DxgiFormat::DXGI_FORMAT_R32G32B32A32_FLOAT => {
+            PixelData::F32(uncompressed::decode_f32(src))
+        }
+        DxgiFormat::DXGI_FORMAT_R11G11B10_FLOAT => {
+            PixelData::F32(uncompressed::decode_r11g11b10_float(src))
+        }
+        DxgiFormat::DXGI_FORMAT_R9G9B9E5_SHAREDEXP => {
+            PixelData::F32(uncompressed::decode_r9g9b9e5(src))
+        }
+        _ => {
+            return Err(EddsError::UnknownImageDataFormat(format!(
+                "{:?}",
+                dx10_header.dxgi_format
+            )))
+        }
+    })
 }
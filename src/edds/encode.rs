@@ -0,0 +1,82 @@
+//! Encoders from RGBA8 to the stored pixel formats.
+
+use crate::core::errors::EddsError;
+
+use super::{bc7_encoder::encode_bc7, DxgiFormat, EncodeQuality};
+
+/// Encodes a `width` x `height` RGBA8 image to `format`.
+pub(crate) fn encode_rgba8(
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    format: DxgiFormat,
+    quality: EncodeQuality,
+) -> Result<Vec<u8>, EddsError> {
+    match format {
+        DxgiFormat::DXGI_FORMAT_R8G8B8A8_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_R8G8B8A8_UNORM
+        | DxgiFormat::DXGI_FORMAT_R8G8B8A8_UNORM_SRGB => Ok(rgba.to_vec()),
+        DxgiFormat::DXGI_FORMAT_B8G8R8A8_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_B8G8R8A8_UNORM
+        | DxgiFormat::DXGI_FORMAT_B8G8R8A8_UNORM_SRGB => Ok(rgba
+            .chunks_exact(4)
+            .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+            .collect()),
+        DxgiFormat::DXGI_FORMAT_B8G8R8X8_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_B8G8R8X8_UNORM
+        | DxgiFormat::DXGI_FORMAT_B8G8R8X8_UNORM_SRGB => Ok(rgba
+            .chunks_exact(4)
+            .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], 255])
+            .collect()),
+        DxgiFormat::DXGI_FORMAT_BC1_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_BC1_UNORM
+        | DxgiFormat::DXGI_FORMAT_BC1_UNORM_SRGB => Ok(encode_texpresso(
+            texpresso::Format::Bc1,
+            rgba,
+            width,
+            height,
+            quality,
+        )),
+        DxgiFormat::DXGI_FORMAT_BC3_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_BC3_UNORM
+        | DxgiFormat::DXGI_FORMAT_BC3_UNORM_SRGB => Ok(encode_texpresso(
+            texpresso::Format::Bc3,
+            rgba,
+            width,
+            height,
+            quality,
+        )),
+        DxgiFormat::DXGI_FORMAT_BC4_TYPELESS | DxgiFormat::DXGI_FORMAT_BC4_UNORM => Ok(
+            encode_texpresso(texpresso::Format::Bc4, rgba, width, height, quality),
+        ),
+        DxgiFormat::DXGI_FORMAT_BC5_TYPELESS | DxgiFormat::DXGI_FORMAT_BC5_UNORM => Ok(
+            encode_texpresso(texpresso::Format::Bc5, rgba, width, height, quality),
+        ),
+        DxgiFormat::DXGI_FORMAT_BC7_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_BC7_UNORM
+        | DxgiFormat::DXGI_FORMAT_BC7_UNORM_SRGB => Ok(encode_bc7(rgba, width, height, quality)),
+        format => Err(EddsError::UnsupportedEncodeFormat(format!("{:?}", format))),
+    }
+}
+
+/// Encodes BC1 and BC3 from RGBA, BC4 from red and BC5 from red and green.
+fn encode_texpresso(
+    format: texpresso::Format,
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    quality: EncodeQuality,
+) -> Vec<u8> {
+    let params = texpresso::Params {
+        algorithm: match quality {
+            EncodeQuality::Fast => texpresso::Algorithm::RangeFit,
+            EncodeQuality::Normal => texpresso::Algorithm::ClusterFit,
+            EncodeQuality::Slow => texpresso::Algorithm::IterativeClusterFit,
+        },
+        ..Default::default()
+    };
+
+    let mut output = vec![0; format.compressed_size(width, height)];
+    format.compress(rgba, width, height, params, &mut output);
+    output
+}
@@ -0,0 +1,359 @@
+use crate::core::errors::EddsError;
+
+use super::{
+    dds_header::{DdsHeader, DdsPixelFormat, DxgiFormat},
+    DdsPixelFormatEnum, DdsPixelformatFlags, FourCCEnum,
+};
+
+/// How the colour channels of decoded pixels are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// The format is an `_SRGB` DXGI format.
+    Srgb,
+    /// The format is a linear DXGI format (UNORM, SNORM or float).
+    Linear,
+    /// Legacy headers and typeless DXGI formats leave the interpretation to the
+    /// consumer of the texture.
+    Unspecified,
+}
+
+impl ColorSpace {
+    fn from_header(header: &DdsHeader) -> ColorSpace {
+        match &header.dx10_header {
+            Some(dx10_header) if dx10_header.dxgi_format.is_srgb() => ColorSpace::Srgb,
+            Some(dx10_header) if dx10_header.dxgi_format.is_typeless() => ColorSpace::Unspecified,
+            Some(_) => ColorSpace::Linear,
+            None => ColorSpace::Unspecified,
+        }
+    }
+}
+
+/// Format of the pixel data as stored in the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageFormat {
+    Dxgi(DxgiFormat),
+    FourCC(FourCCEnum),
+    /// Uncompressed legacy format described by the bit masks of the pixel format.
+    PixelFormat(DdsPixelFormatEnum),
+    /// Uncompressed legacy format whose masks match no known format, with the
+    /// `rgb_bit_count` of the pixel format.
+    Masked(u32),
+}
+
+impl StorageFormat {
+    pub fn from_header(header: &DdsHeader) -> StorageFormat {
+        match (&header.dx10_header, header.ddspf.four_cc) {
+            (Some(dx10_header), _) => StorageFormat::Dxgi(dx10_header.dxgi_format),
+            (None, FourCCEnum::None) => match header.get_pixel_format() {
+                DdsPixelFormatEnum::Unknown => StorageFormat::Masked(header.ddspf.rgb_bit_count),
+                format => StorageFormat::PixelFormat(format),
+            },
+            (None, four_cc) => StorageFormat::FourCC(four_cc),
+        }
+    }
+
+    /// Returns the width of a block in pixels and its size in bytes, uncompressed
+    /// formats use blocks of a single pixel. `None` for formats that can't be decoded.
+    pub fn block_layout(&self) -> Option<(usize, usize)> {
+        match self {
+            StorageFormat::Dxgi(format) => dxgi_block_layout(format),
+            StorageFormat::FourCC(four_cc) => match four_cc {
+                FourCCEnum::DXT1 | FourCCEnum::ATI1 | FourCCEnum::BC4U | FourCCEnum::BC4S => {
+                    Some((4, 8))
+                }
+                FourCCEnum::DXT2
+                | FourCCEnum::DXT3
+                | FourCCEnum::DXT4
+                | FourCCEnum::DXT5
+                | FourCCEnum::ATI2
+                | FourCCEnum::BC5U
+                | FourCCEnum::BC5S => Some((4, 16)),
+                _ => None,
+            },
+            StorageFormat::PixelFormat(format) => match format {
+                DdsPixelFormatEnum::D3DFMT_A4L4
+                | DdsPixelFormatEnum::D3DFMT_A8
+                | DdsPixelFormatEnum::D3DFMT_L8 => Some((1, 1)),
+                DdsPixelFormatEnum::D3DFMT_A1R5G5B5
+                | DdsPixelFormatEnum::D3DFMT_A4R4G4B4
+                | DdsPixelFormatEnum::D3DFMT_A8L8
+                | DdsPixelFormatEnum::D3DFMT_A8R3G3B2
+                | DdsPixelFormatEnum::D3DFMT_L16
+                | DdsPixelFormatEnum::D3FMT_R5G6B5
+                | DdsPixelFormatEnum::D3DFMT_X1R5G5B5
+                | DdsPixelFormatEnum::D3DFMT_X4R4G4B4 => Some((1, 2)),
+                DdsPixelFormatEnum::D3DFMT_R8G8B8 => Some((1, 3)),
+                DdsPixelFormatEnum::D3DFMT_A2B10G10R10
+                | DdsPixelFormatEnum::D3DFMT_A2R10G10B10
+                | DdsPixelFormatEnum::D3DFMT_A8B8G8R8
+                | DdsPixelFormatEnum::D3DFMT_A8R8G8B8
+                | DdsPixelFormatEnum::D3DFMT_G16R16
+                | DdsPixelFormatEnum::D3DFMT_X8B8G8R8
+                | DdsPixelFormatEnum::D3DFMT_X8R8G8B8 => Some((1, 4)),
+                DdsPixelFormatEnum::Unknown => None,
+            },
+            StorageFormat::Masked(bit_count) if *bit_count != 0 && bit_count % 8 == 0 => {
+                Some((1, *bit_count as usize / 8))
+            }
+            StorageFormat::Masked(_) => None,
+        }
+    }
+
+    /// Size in bytes of the stored pixel data of a `width` x `height` image.
+    /// Fails for formats that can't be decoded and sizes that don't fit in `usize`.
+    pub fn data_size(&self, width: usize, height: usize) -> Result<usize, EddsError> {
+        let (block_width, block_size) = self
+            .block_layout()
+            .ok_or_else(|| EddsError::UnknownImageDataFormat(format!("{:?}", self)))?;
+        width
+            .div_ceil(block_width)
+            .checked_mul(height.div_ceil(block_width))
+            .and_then(|blocks| blocks.checked_mul(block_size))
+            .ok_or(EddsError::ImageTooLarge(width, height))
+    }
+
+    pub fn is_block_compressed(&self) -> bool {
+        matches!(self.block_layout(), Some((4, _)))
+    }
+}
+
+fn dxgi_block_layout(format: &DxgiFormat) -> Option<(usize, usize)> {
+    Some(match format {
+        DxgiFormat::DXGI_FORMAT_BC1_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_BC1_UNORM
+        | DxgiFormat::DXGI_FORMAT_BC1_UNORM_SRGB
+        | DxgiFormat::DXGI_FORMAT_BC4_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_BC4_UNORM
+        | DxgiFormat::DXGI_FORMAT_BC4_SNORM => (4, 8),
+        DxgiFormat::DXGI_FORMAT_BC2_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_BC2_UNORM
+        | DxgiFormat::DXGI_FORMAT_BC2_UNORM_SRGB
+        | DxgiFormat::DXGI_FORMAT_BC3_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_BC3_UNORM
+        | DxgiFormat::DXGI_FORMAT_BC3_UNORM_SRGB
+        | DxgiFormat::DXGI_FORMAT_BC5_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_BC5_UNORM
+        | DxgiFormat::DXGI_FORMAT_BC5_SNORM
+        | DxgiFormat::DXGI_FORMAT_BC6H_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_BC6H_UF16
+        | DxgiFormat::DXGI_FORMAT_BC6H_SF16
+        | DxgiFormat::DXGI_FORMAT_BC7_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_BC7_UNORM
+        | DxgiFormat::DXGI_FORMAT_BC7_UNORM_SRGB => (4, 16),
+        DxgiFormat::DXGI_FORMAT_R32G32B32A32_FLOAT => (1, 16),
+        DxgiFormat::DXGI_FORMAT_R32G32B32_FLOAT => (1, 12),
+        DxgiFormat::DXGI_FORMAT_R16G16B16A16_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_R16G16B16A16_UNORM
+        | DxgiFormat::DXGI_FORMAT_R16G16B16A16_FLOAT => (1, 8),
+        DxgiFormat::DXGI_FORMAT_R8G8B8A8_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_R8G8B8A8_UNORM
+        | DxgiFormat::DXGI_FORMAT_R8G8B8A8_UNORM_SRGB
+        | DxgiFormat::DXGI_FORMAT_R8G8B8A8_SNORM
+        | DxgiFormat::DXGI_FORMAT_B8G8R8A8_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_B8G8R8A8_UNORM
+        | DxgiFormat::DXGI_FORMAT_B8G8R8A8_UNORM_SRGB
+        | DxgiFormat::DXGI_FORMAT_B8G8R8X8_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_B8G8R8X8_UNORM
+        | DxgiFormat::DXGI_FORMAT_B8G8R8X8_UNORM_SRGB
+        | DxgiFormat::DXGI_FORMAT_R16G16_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_R16G16_UNORM
+        | DxgiFormat::DXGI_FORMAT_R10G10B10A2_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_R10G10B10A2_UNORM
+        | DxgiFormat::DXGI_FORMAT_R11G11B10_FLOAT
+        | DxgiFormat::DXGI_FORMAT_R9G9B9E5_SHAREDEXP
+        | DxgiFormat::DXGI_FORMAT_R32_FLOAT => (1, 4),
+        DxgiFormat::DXGI_FORMAT_R16_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_R16_UNORM
+        | DxgiFormat::DXGI_FORMAT_R16_FLOAT
+        | DxgiFormat::DXGI_FORMAT_R8G8_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_R8G8_UNORM
+        | DxgiFormat::DXGI_FORMAT_R8G8_SNORM
+        | DxgiFormat::DXGI_FORMAT_B5G6R5_UNORM
+        | DxgiFormat::DXGI_FORMAT_B5G5R5A1_UNORM
+        | DxgiFormat::DXGI_FORMAT_B4G4R4A4_UNORM => (1, 2),
+        DxgiFormat::DXGI_FORMAT_R8_TYPELESS
+        | DxgiFormat::DXGI_FORMAT_R8_UNORM
+        | DxgiFormat::DXGI_FORMAT_R8_SNORM
+        | DxgiFormat::DXGI_FORMAT_A8_UNORM => (1, 1),
+        _ => return None,
+    })
+}
+
+/// How a decoded channel value is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleType {
+    /// Normalized integers, `0` maps to 0.0 and the maximum value to 1.0.
+    /// SNORM formats are remapped to this range while decoding.
+    Unorm,
+    /// IEEE floats.
+    Float,
+}
+
+/// Layout of the decoded pixels in [`PixelData`](super::PixelData).
+///
+/// Channels are interleaved in the order L, RGB or RGBA depending on
+/// `channels`, each channel takes `bit_depth` bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodedFormat {
+    pub channels: u8,
+    pub bit_depth: u8,
+    pub sample_type: SampleType,
+    pub color_space: ColorSpace,
+}
+
+impl DecodedFormat {
+    /// Returns the format the decoders produce for the texture described by `header`.
+    pub fn from_header(header: &DdsHeader) -> Result<DecodedFormat, EddsError> {
+        let (channels, bit_depth, sample_type) = match &header.dx10_header {
+            Some(dx10_header) => Self::layout_for_dxgi_format(&dx10_header.dxgi_format)?,
+            None => Self::layout_for_four_cc(header)?,
+        };
+
+        Ok(DecodedFormat {
+            channels,
+            bit_depth,
+            sample_type,
+            color_space: ColorSpace::from_header(header),
+        })
+    }
+
+    /// Size of a single decoded pixel in bytes.
+    pub fn bytes_per_pixel(&self) -> usize {
+        self.channels as usize * self.bit_depth as usize / 8
+    }
+
+    fn layout_for_four_cc(header: &DdsHeader) -> Result<(u8, u8, SampleType), EddsError> {
+        Ok(match &header.ddspf.four_cc {
+            FourCCEnum::None => return Self::layout_for_pixel_format(header),
+            FourCCEnum::DXT1
+            | FourCCEnum::DXT2
+            | FourCCEnum::DXT3
+            | FourCCEnum::DXT4
+            | FourCCEnum::DXT5
+            | FourCCEnum::ATI2
+            | FourCCEnum::BC5U
+            | FourCCEnum::BC5S => (4, 8, SampleType::Unorm),
+            FourCCEnum::ATI1 | FourCCEnum::BC4U | FourCCEnum::BC4S => (1, 8, SampleType::Unorm),
+            ni_four_cc => {
+                return Err(EddsError::UnknownImageDataFormat(format!(
+                    "{:?}",
+                    ni_four_cc
+                )))
+            }
+        })
+    }
+
+    fn layout_for_pixel_format(header: &DdsHeader) -> Result<(u8, u8, SampleType), EddsError> {
+        let format = header.get_pixel_format();
+        match format {
+            DdsPixelFormatEnum::D3DFMT_L8 => return Ok((1, 8, SampleType::Unorm)),
+            DdsPixelFormatEnum::D3DFMT_L16 => return Ok((1, 16, SampleType::Unorm)),
+            _ => {}
+        }
+
+        let (_, masks) = pixel_format_layout(&header.ddspf, &format)?;
+        if masks.iter().any(|mask| mask.count_ones() > 8) {
+            Ok((4, 16, SampleType::Unorm))
+        } else {
+            Ok((4, 8, SampleType::Unorm))
+        }
+    }
+
+    fn layout_for_dxgi_format(format: &DxgiFormat) -> Result<(u8, u8, SampleType), EddsError> {
+        Ok(match format {
+            DxgiFormat::DXGI_FORMAT_BC1_TYPELESS
+            | DxgiFormat::DXGI_FORMAT_BC1_UNORM
+            | DxgiFormat::DXGI_FORMAT_BC1_UNORM_SRGB
+            | DxgiFormat::DXGI_FORMAT_BC2_TYPELESS
+            | DxgiFormat::DXGI_FORMAT_BC2_UNORM
+            | DxgiFormat::DXGI_FORMAT_BC2_UNORM_SRGB
+            | DxgiFormat::DXGI_FORMAT_BC3_TYPELESS
+            | DxgiFormat::DXGI_FORMAT_BC3_UNORM
+            | DxgiFormat::DXGI_FORMAT_BC3_UNORM_SRGB
+            | DxgiFormat::DXGI_FORMAT_BC5_TYPELESS
+            | DxgiFormat::DXGI_FORMAT_BC5_UNORM
+            | DxgiFormat::DXGI_FORMAT_BC5_SNORM
+            | DxgiFormat::DXGI_FORMAT_BC7_TYPELESS
+            | DxgiFormat::DXGI_FORMAT_BC7_UNORM
+            | DxgiFormat::DXGI_FORMAT_BC7_UNORM_SRGB
+            | DxgiFormat::DXGI_FORMAT_R8G8B8A8_TYPELESS
+            | DxgiFormat::DXGI_FORMAT_R8G8B8A8_UNORM
+            | DxgiFormat::DXGI_FORMAT_R8G8B8A8_UNORM_SRGB
+            | DxgiFormat::DXGI_FORMAT_R8G8B8A8_SNORM
+            | DxgiFormat::DXGI_FORMAT_B8G8R8A8_TYPELESS
+            | DxgiFormat::DXGI_FORMAT_B8G8R8A8_UNORM
+            | DxgiFormat::DXGI_FORMAT_B8G8R8A8_UNORM_SRGB
+            | DxgiFormat::DXGI_FORMAT_B8G8R8X8_TYPELESS
+            | DxgiFormat::DXGI_FORMAT_B8G8R8X8_UNORM
+            | DxgiFormat::DXGI_FORMAT_B8G8R8X8_UNORM_SRGB
+            | DxgiFormat::DXGI_FORMAT_R8G8_TYPELESS
+            | DxgiFormat::DXGI_FORMAT_R8G8_UNORM
+            | DxgiFormat::DXGI_FORMAT_R8G8_SNORM
+            | DxgiFormat::DXGI_FORMAT_A8_UNORM
+            | DxgiFormat::DXGI_FORMAT_B5G6R5_UNORM
+            | DxgiFormat::DXGI_FORMAT_B5G5R5A1_UNORM
+            | DxgiFormat::DXGI_FORMAT_B4G4R4A4_UNORM => (4, 8, SampleType::Unorm),
+            DxgiFormat::DXGI_FORMAT_BC4_TYPELESS
+            | DxgiFormat::DXGI_FORMAT_BC4_UNORM
+            | DxgiFormat::DXGI_FORMAT_BC4_SNORM
+            | DxgiFormat::DXGI_FORMAT_R8_TYPELESS
+            | DxgiFormat::DXGI_FORMAT_R8_UNORM
+            | DxgiFormat::DXGI_FORMAT_R8_SNORM => (1, 8, SampleType::Unorm),
+            DxgiFormat::DXGI_FORMAT_R16_TYPELESS | DxgiFormat::DXGI_FORMAT_R16_UNORM => {
+                (1, 16, SampleType::Unorm)
+            }
+            DxgiFormat::DXGI_FORMAT_R16G16B16A16_TYPELESS
+            | DxgiFormat::DXGI_FORMAT_R16G16B16A16_UNORM
+            | DxgiFormat::DXGI_FORMAT_R16G16_TYPELESS
+            | DxgiFormat::DXGI_FORMAT_R16G16_UNORM
+            | DxgiFormat::DXGI_FORMAT_R10G10B10A2_TYPELESS
+            | DxgiFormat::DXGI_FORMAT_R10G10B10A2_UNORM => (4, 16, SampleType::Unorm),
+            DxgiFormat::DXGI_FORMAT_R16_FLOAT | DxgiFormat::DXGI_FORMAT_R32_FLOAT => {
+                (1, 32, SampleType::Float)
+            }
+            DxgiFormat::DXGI_FORMAT_BC6H_TYPELESS
+            | DxgiFormat::DXGI_FORMAT_BC6H_UF16
+            | DxgiFormat::DXGI_FORMAT_BC6H_SF16
+            | DxgiFormat::DXGI_FORMAT_R32G32B32_FLOAT
+            | DxgiFormat::DXGI_FORMAT_R11G11B10_FLOAT
+            | DxgiFormat::DXGI_FORMAT_R9G9B9E5_SHAREDEXP => (3, 32, SampleType::Float),
+            DxgiFormat::DXGI_FORMAT_R16G16B16A16_FLOAT
+            | DxgiFormat::DXGI_FORMAT_R32G32B32A32_FLOAT => (4, 32, SampleType::Float),
+            _ => return Err(EddsError::UnknownImageDataFormat(format!("{:?}", format))),
+        })
+    }
+}
+
+/// Returns the bytes per pixel and the `[r, g, b, a]` masks used to decode a
+/// legacy pixel format. Luminance formats use the red mask for all colour channels.
+pub(crate) fn pixel_format_layout(
+    pf: &DdsPixelFormat,
+    format: &DdsPixelFormatEnum,
+) -> Result<(usize, [u32; 4]), EddsError> {
+    let bytes_per_pixel = match pf.rgb_bit_count {
+        8 | 16 | 24 | 32 => (pf.rgb_bit_count / 8) as usize,
+        _ => {
+            return Err(EddsError::UnknownImageDataFormat(format!(
+                "{:?} with {} bits per pixel",
+                pf, pf.rgb_bit_count
+            )))
+        }
+    };
+
+    let luminance = pf.flags.contains(DdsPixelformatFlags::DDPF_LUMINANCE)
+        || matches!(
+            format,
+            DdsPixelFormatEnum::D3DFMT_A8L8 | DdsPixelFormatEnum::D3DFMT_A4L4
+        );
+    let masks = if luminance {
+        [pf.r_bit_mask, pf.r_bit_mask, pf.r_bit_mask, pf.a_bit_mask]
+    } else {
+        [pf.r_bit_mask, pf.g_bit_mask, pf.b_bit_mask, pf.a_bit_mask]
+    };
+
+    if masks.iter().all(|mask| *mask == 0) {
+        return Err(EddsError::UnknownImageDataFormat(format!("{:?}", pf)));
+    }
+
+    Ok((bytes_per_pixel, masks))
+}
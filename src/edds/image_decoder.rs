@@ -0,0 +1,228 @@
+//! Integration with the `image` crate, available with the `image` feature.
+//!
+//! ```no_run
+//! use std::{fs::File, io::BufReader};
+//!
+//! use eff::edds::EddsDecoder;
+//! use image::DynamicImage;
+//!
+//! let input = BufReader::new(File::open("texture.edds").unwrap());
+//! let image = DynamicImage::from_decoder(EddsDecoder::new(input).unwrap()).unwrap();
+//! ```
+
+use std::io::{BufRead, Cursor, Seek};
+
+use image::{
+    error::{DecodingError, ImageFormatHint},
+    ColorType, DynamicImage, ImageBuffer, ImageDecoder, ImageError, ImageResult, Luma, Pixel, Rgb,
+    Rgba,
+};
+
+use crate::core::errors::EddsError;
+
+use super::{DecodeOptions, DecodedFormat, LazyEdds, Mipmap, PixelData, SampleType};
+
+/// [`ImageDecoder`] for a single mip of an EDDS file, the largest one unless
+/// another one is selected.
+///
+/// Cubemaps and arrays fail to decode until one of their surfaces is selected.
+/// Only the first depth slice of volumes is decoded.
+#[derive(Debug)]
+pub struct EddsDecoder<R> {
+    edds: LazyEdds<R>,
+    index: usize,
+    surface: Option<usize>,
+    color_type: ColorType,
+}
+
+impl<R> EddsDecoder<R>
+where
+    R: Seek + BufRead,
+{
+    pub fn new(input: R) -> Result<EddsDecoder<R>, EddsError> {
+        EddsDecoder::with_options(input, DecodeOptions::default())
+    }
+
+    pub fn with_options(input: R, options: DecodeOptions) -> Result<EddsDecoder<R>, EddsError> {
+        let edds = LazyEdds::from_with_options(input, options)?;
+        let color_type = color_type(&edds.format)?;
+        let index = match edds.mip_count() {
+            0 => return Err(EddsError::MipmapIndexOutOfRange(0, 0)),
+            mip_count => mip_count - 1,
+        };
+
+        Ok(EddsDecoder {
+            edds,
+            index,
+            surface: None,
+            color_type,
+        })
+    }
+
+    /// Selects the mip to decode, indexed like [`LazyEdds`] with index 0 being
+    /// the smallest mip.
+    pub fn select_mipmap(&mut self, index: usize) -> Result<(), EddsError> {
+        if index >= self.edds.mip_count() {
+            return Err(EddsError::MipmapIndexOutOfRange(
+                index,
+                self.edds.mip_count(),
+            ));
+        }
+
+        self.index = index;
+        Ok(())
+    }
+
+    /// Selects the cube face or array layer to decode, indexed like the
+    /// surfaces of [`TextureLayout`](super::TextureLayout), whose order is
+    /// still provisional.
+    pub fn select_surface(&mut self, surface: usize) -> Result<(), EddsError> {
+        let surface_count = self.edds.info.layout.surface_count();
+        if surface >= surface_count {
+            return Err(EddsError::SurfaceIndexOutOfRange(surface, surface_count));
+        }
+
+        self.surface = Some(surface);
+        Ok(())
+    }
+
+    fn decode(mut self) -> Result<Vec<u8>, EddsError> {
+        let mipmap = self.edds.mipmap(self.index)?;
+        let image = match self.surface {
+            Some(surface) => mipmap.surface_to_dynamic_image(surface)?,
+            None => mipmap.to_dynamic_image()?,
+        };
+
+        Ok(image.into_bytes())
+    }
+}
+
+impl<'a, R> ImageDecoder<'a> for EddsDecoder<R>
+where
+    R: Seek + BufRead + 'a,
+{
+    type Reader = Cursor<Vec<u8>>;
+
+    fn dimensions(&self) -> (u32, u32) {
+        let mipmap = &self.edds.info.mipmaps[self.index];
+        (mipmap.width as u32, mipmap.height as u32)
+    }
+
+    fn color_type(&self) -> ColorType {
+        self.color_type
+    }
+
+    fn into_reader(self) -> ImageResult<Self::Reader> {
+        Ok(Cursor::new(self.decode()?))
+    }
+
+    fn read_image(self, buf: &mut [u8]) -> ImageResult<()> {
+        buf.copy_from_slice(&self.decode()?);
+        Ok(())
+    }
+}
+
+impl Mipmap {
+    /// Converts the mip of a texture with a single surface to an image of the
+    /// matching [`ColorType`], see [`Mipmap::data`].
+    ///
+    /// Single channel float formats have no `image` equivalent and become
+    /// grey RGB. Only the first depth slice of a volume mip is converted.
+    pub fn to_dynamic_image(&self) -> Result<DynamicImage, EddsError> {
+        self.pixels_to_dynamic_image(self.data()?)
+    }
+
+    /// Like [`Mipmap::to_dynamic_image`] for one cube face or array layer.
+    pub fn surface_to_dynamic_image(&self, surface: usize) -> Result<DynamicImage, EddsError> {
+        let data = self
+            .surfaces
+            .get(surface)
+            .ok_or(EddsError::SurfaceIndexOutOfRange(
+                surface,
+                self.surfaces.len(),
+            ))?;
+        self.pixels_to_dynamic_image(data)
+    }
+
+    fn pixels_to_dynamic_image(&self, data: &PixelData) -> Result<DynamicImage, EddsError> {
+        let (width, height) = (self.width as u32, self.height as u32);
+        let len = self.width * self.height * self.format.channels as usize;
+
+        let image = match (color_type(&self.format)?, data) {
+            (ColorType::L8, PixelData::U8(data)) => {
+                buffer::<Luma<u8>>(width, height, data, len).map(DynamicImage::ImageLuma8)
+            }
+            (ColorType::Rgb8, PixelData::U8(data)) => {
+                buffer::<Rgb<u8>>(width, height, data, len).map(DynamicImage::ImageRgb8)
+            }
+            (ColorType::Rgba8, PixelData::U8(data)) => {
+                buffer::<Rgba<u8>>(width, height, data, len).map(DynamicImage::ImageRgba8)
+            }
+            (ColorType::L16, PixelData::U16(data)) => {
+                buffer::<Luma<u16>>(width, height, data, len).map(DynamicImage::ImageLuma16)
+            }
+            (ColorType::Rgb16, PixelData::U16(data)) => {
+                buffer::<Rgb<u16>>(width, height, data, len).map(DynamicImage::ImageRgb16)
+            }
+            (ColorType::Rgba16, PixelData::U16(data)) => {
+                buffer::<Rgba<u16>>(width, height, data, len).map(DynamicImage::ImageRgba16)
+            }
+            (ColorType::Rgb32F, PixelData::F32(data)) if self.format.channels == 1 => {
+                let grey = data.get(..len).map(|data| {
+                    data.iter()
+                        .flat_map(|&value| [value; 3])
+                        .collect::<Vec<_>>()
+                });
+                grey.and_then(|grey| ImageBuffer::from_raw(width, height, grey))
+                    .map(DynamicImage::ImageRgb32F)
+            }
+            (ColorType::Rgb32F, PixelData::F32(data)) => {
+                buffer::<Rgb<f32>>(width, height, data, len).map(DynamicImage::ImageRgb32F)
+            }
+            (ColorType::Rgba32F, PixelData::F32(data)) => {
+                buffer::<Rgba<f32>>(width, height, data, len).map(DynamicImage::ImageRgba32F)
+            }
+            _ => None,
+        };
+
+        image.ok_or_else(|| EddsError::UnknownImageDataFormat(format!("{:?}", self.format)))
+    }
+}
+
+impl From<EddsError> for ImageError {
+    fn from(error: EddsError) -> Self {
+        ImageError::Decoding(DecodingError::new(
+            ImageFormatHint::Name("EDDS".to_string()),
+            error,
+        ))
+    }
+}
+
+/// [`ColorType`] of images converted from mips in `format`.
+fn color_type(format: &DecodedFormat) -> Result<ColorType, EddsError> {
+    Ok(
+        match (format.sample_type, format.channels, format.bit_depth) {
+            (SampleType::Unorm, 1, 8) => ColorType::L8,
+            (SampleType::Unorm, 3, 8) => ColorType::Rgb8,
+            (SampleType::Unorm, 4, 8) => ColorType::Rgba8,
+            (SampleType::Unorm, 1, 16) => ColorType::L16,
+            (SampleType::Unorm, 3, 16) => ColorType::Rgb16,
+            (SampleType::Unorm, 4, 16) => ColorType::Rgba16,
+            (SampleType::Float, 1 | 3, 32) => ColorType::Rgb32F,
+            (SampleType::Float, 4, 32) => ColorType::Rgba32F,
+            _ => return Err(EddsError::UnknownImageDataFormat(format!("{:?}", format))),
+        },
+    )
+}
+
+fn buffer<P>(
+    width: u32,
+    height: u32,
+    data: &[P::Subpixel],
+    len: usize,
+) -> Option<ImageBuffer<P, Vec<P::Subpixel>>>
+where
+    P: Pixel,
+{
+    ImageBuffer::from_raw(width, height, data.get(..len)?.to_vec())
+}
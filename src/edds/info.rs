@@ -0,0 +1,120 @@
+use std::io::{BufRead, Seek};
+
+use crate::core::{errors::EddsError, read::ReadExtTrait};
+
+use super::{
+    DdsHeader, Edds, HeaderDiagnostic, HeaderMode, MipmapBlock, MipmapType, StorageFormat,
+    TextureLayout,
+};
+
+/// Header and mip table of an EDDS file, read without touching the pixel data.
+#[derive(Debug, Clone)]
+pub struct EddsInfo {
+    pub header: DdsHeader,
+    pub width: usize,
+    pub height: usize,
+    pub format: StorageFormat,
+    pub layout: TextureLayout,
+    /// Problems found in the header, see [`DdsHeader::diagnostics`].
+    pub warnings: Vec<HeaderDiagnostic>,
+    /// Mips in file order, smallest mip first.
+    pub mipmaps: Vec<MipmapInfo>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MipmapInfo {
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    pub block: MipmapBlock,
+    /// Position of the mip data from the start of the file.
+    pub offset: u64,
+}
+
+impl EddsInfo {
+    /// Reads the header and the mip table, leaving `input` at the start of the
+    /// first mip.
+    pub fn from<I>(input: &mut I) -> Result<EddsInfo, EddsError>
+    where
+        I: Seek + BufRead,
+    {
+        EddsInfo::from_with_mode(input, HeaderMode::Lenient)
+    }
+
+    /// Like [`EddsInfo::from`], with [`HeaderMode::Strict`] any header
+    /// diagnostic is an [`EddsError::InvalidHeader`].
+    pub fn from_with_mode<I>(input: &mut I, mode: HeaderMode) -> Result<EddsInfo, EddsError>
+    where
+        I: Seek + BufRead,
+    {
+        let header = DdsHeader::from_stream(input)?;
+        let warnings = header.diagnostics();
+        if mode == HeaderMode::Strict && !warnings.is_empty() {
+            return Err(EddsError::InvalidHeader(warnings));
+        }
+        let mut blocks = Vec::new();
+
+        for _ in 0..header.mip_map_count {
+            let data_type = input.read_string_lossy(4)?;
+            let size = input.read_u32()?;
+            blocks.push(MipmapBlock {
+                data_type: match data_type.as_str() {
+                    "COPY" => MipmapType::COPY,
+                    "LZ4 " => MipmapType::LZ4,
+                    unk => return Err(EddsError::UnknownImageDataType(format!("{:?}", unk))),
+                },
+                size,
+            });
+        }
+
+        let layout = TextureLayout::from_header(&header);
+        let mut offset = input.stream_position()?;
+        let mipmaps = blocks
+            .into_iter()
+            .zip((1..(header.mip_map_count + 1)).rev())
+            .map(|(block, i)| {
+                let mipmap = MipmapInfo {
+                    width: Edds::get_dim_for_index(header.width, i),
+                    height: Edds::get_dim_for_index(header.height, i),
+                    depth: layout.depth_for_index(i),
+                    block,
+                    offset,
+                };
+                offset += block.size as u64;
+                mipmap
+            })
+            .collect();
+
+        Ok(EddsInfo {
+            width: header.width as usize,
+            height: header.height as usize,
+            format: StorageFormat::from_header(&header),
+            layout,
+            warnings,
+            header,
+            mipmaps,
+        })
+    }
+
+    pub fn mip_count(&self) -> usize {
+        self.mipmaps.len()
+    }
+
+    /// Size of the mip at `index` once decompressed, covering every surface.
+    /// `None` for formats that can't be decoded and sizes that overflow.
+    pub fn mip_data_size(&self, index: usize) -> Option<usize> {
+        let mipmap = self.mipmaps.get(index)?;
+        let surface_size = self.format.data_size(mipmap.width, mipmap.height).ok()?;
+        surface_size
+            .checked_mul(mipmap.depth)?
+            .checked_mul(self.layout.surface_count())
+    }
+
+    /// Total size of the stored mip data, excluding the headers and the mip table.
+    pub fn stored_size(&self) -> u64 {
+        self.mipmaps
+            .iter()
+            .map(|mipmap| mipmap.block.size as u64)
+            .sum()
+    }
+}
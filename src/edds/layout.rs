@@ -0,0 +1,156 @@
+use super::{D3D10_Resource_Dimension, DdsCaps2Flags, DdsHeader, Edds};
+
+/// `D3D11_RESOURCE_MISC_TEXTURECUBE` in the `misc_flag` of the DX10 header.
+const DX10_MISC_TEXTURECUBE: u32 = 0x4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureKind {
+    Texture2D,
+    Cubemap,
+    Volume,
+}
+
+/// Faces of a cubemap, in the order they are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+const CUBE_FACES: [(CubeFace, DdsCaps2Flags); 6] = [
+    (
+        CubeFace::PositiveX,
+        DdsCaps2Flags::DDSCAPS2_CUBEMAP_POSITIVEX,
+    ),
+    (
+        CubeFace::NegativeX,
+        DdsCaps2Flags::DDSCAPS2_CUBEMAP_NEGATIVEX,
+    ),
+    (
+        CubeFace::PositiveY,
+        DdsCaps2Flags::DDSCAPS2_CUBEMAP_POSITIVEY,
+    ),
+    (
+        CubeFace::NegativeY,
+        DdsCaps2Flags::DDSCAPS2_CUBEMAP_NEGATIVEY,
+    ),
+    (
+        CubeFace::PositiveZ,
+        DdsCaps2Flags::DDSCAPS2_CUBEMAP_POSITIVEZ,
+    ),
+    (
+        CubeFace::NegativeZ,
+        DdsCaps2Flags::DDSCAPS2_CUBEMAP_NEGATIVEZ,
+    ),
+];
+
+/// Surfaces stored in each mip of a texture.
+///
+/// Every mip holds the data of all its surfaces back to back, array layer by
+/// array layer and face by face within a layer. A surface of a volume texture
+/// holds all depth slices of the mip, which halve like width and height.
+///
+/// This order is an assumption: it mirrors how EDDS stores whole mips, but no
+/// cubemap, array or volume texture exported by Enfusion has been checked
+/// against it, the fixtures covering it are synthetic. Until one has, the
+/// split into surfaces is provisional and may change: a different order would
+/// hand out the same bytes under other face, layer and slice indices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextureLayout {
+    pub kind: TextureKind,
+    /// Number of array layers, 1 for textures that aren't arrays.
+    pub array_size: usize,
+    /// Faces stored per layer of a cubemap, empty for other kinds.
+    pub faces: Vec<CubeFace>,
+    /// Depth of the largest mip of a volume texture, 1 otherwise.
+    pub depth: usize,
+}
+
+impl TextureLayout {
+    pub fn from_header(header: &DdsHeader) -> TextureLayout {
+        let all_faces = || CUBE_FACES.iter().map(|(face, _)| *face).collect();
+
+        match &header.dx10_header {
+            Some(dx10_header) => {
+                let array_size = std::cmp::max(dx10_header.array_size, 1) as usize;
+                match dx10_header.resource_dimension {
+                    D3D10_Resource_Dimension::D3D10_RESOURCE_DIMENSION_TEXTURE3D => TextureLayout {
+                        kind: TextureKind::Volume,
+                        array_size: 1,
+                        faces: Vec::new(),
+                        depth: std::cmp::max(header.depth, 1) as usize,
+                    },
+                    _ if dx10_header.misc_flag & DX10_MISC_TEXTURECUBE != 0 => TextureLayout {
+                        kind: TextureKind::Cubemap,
+                        array_size,
+                        faces: all_faces(),
+                        depth: 1,
+                    },
+                    _ => TextureLayout {
+                        kind: TextureKind::Texture2D,
+                        array_size,
+                        faces: Vec::new(),
+                        depth: 1,
+                    },
+                }
+            }
+            None if header.caps2.contains(DdsCaps2Flags::DDSCAPS2_CUBEMAP) => {
+                let mut faces: Vec<CubeFace> = CUBE_FACES
+                    .iter()
+                    .filter(|(_, flag)| header.caps2.contains(*flag))
+                    .map(|(face, _)| *face)
+                    .collect();
+                if faces.is_empty() {
+                    faces = all_faces();
+                }
+
+                TextureLayout {
+                    kind: TextureKind::Cubemap,
+                    array_size: 1,
+                    faces,
+                    depth: 1,
+                }
+            }
+            None if header.caps2.contains(DdsCaps2Flags::DDSCAPS2_VOLUME) => TextureLayout {
+                kind: TextureKind::Volume,
+                array_size: 1,
+                faces: Vec::new(),
+                depth: std::cmp::max(header.depth, 1) as usize,
+            },
+            None => TextureLayout {
+                kind: TextureKind::Texture2D,
+                array_size: 1,
+                faces: Vec::new(),
+                depth: 1,
+            },
+        }
+    }
+
+    /// Number of surfaces in every mip.
+    pub fn surface_count(&self) -> usize {
+        self.array_size * std::cmp::max(self.faces.len(), 1)
+    }
+
+    /// Cube face of `surface`, `None` for textures that aren't cubemaps.
+    pub fn face(&self, surface: usize) -> Option<CubeFace> {
+        match self.faces.len() {
+            0 => None,
+            face_count => Some(self.faces[surface % face_count]),
+        }
+    }
+
+    /// Array layer of `surface`.
+    pub fn layer(&self, surface: usize) -> usize {
+        surface / std::cmp::max(self.faces.len(), 1)
+    }
+
+    /// Depth of the mip with the given index, counted from 1 for the largest
+    /// mip like [`Edds::get_dim_for_index`].
+    pub(crate) fn depth_for_index(&self, index: u32) -> usize {
+        Edds::get_dim_for_index(self.depth as u32, index)
+    }
+}
@@ -0,0 +1,79 @@
+use std::io::{BufRead, Seek, SeekFrom};
+
+use crate::core::errors::EddsError;
+
+use super::{DecodeOptions, DecodedFormat, Edds, EddsInfo, Mipmap, RawMipmap};
+
+/// EDDS file that only decodes the mips that are asked for.
+///
+/// Mips are indexed like [`Edds::mipmaps`], index 0 is the smallest mip.
+#[derive(Debug)]
+pub struct LazyEdds<R> {
+    input: R,
+    pub info: EddsInfo,
+    pub format: DecodedFormat,
+    options: DecodeOptions,
+}
+
+impl<R> LazyEdds<R>
+where
+    R: Seek + BufRead,
+{
+    pub fn from(input: R) -> Result<LazyEdds<R>, EddsError> {
+        LazyEdds::from_with_options(input, DecodeOptions::default())
+    }
+
+    pub fn from_with_options(
+        mut input: R,
+        options: DecodeOptions,
+    ) -> Result<LazyEdds<R>, EddsError> {
+        let info = EddsInfo::from_with_mode(&mut input, options.header_mode)?;
+        let format = DecodedFormat::from_header(&info.header)?;
+
+        Ok(LazyEdds {
+            input,
+            info,
+            format,
+            options,
+        })
+    }
+
+    pub fn mip_count(&self) -> usize {
+        self.info.mip_count()
+    }
+
+    /// Reads and decodes the mip at `index`.
+    pub fn mipmap(&mut self, index: usize) -> Result<Mipmap, EddsError> {
+        self.raw_mipmap(index)?
+            .decode(&self.info.header, &self.options)
+    }
+
+    /// Reads the mip at `index` without decoding its pixel data.
+    pub fn raw_mipmap(&mut self, index: usize) -> Result<RawMipmap, EddsError> {
+        let info = *self
+            .info
+            .mipmaps
+            .get(index)
+            .ok_or(EddsError::MipmapIndexOutOfRange(index, self.mip_count()))?;
+
+        self.input.seek(SeekFrom::Start(info.offset))?;
+
+        Ok(RawMipmap {
+            width: info.width,
+            height: info.height,
+            depth: info.depth,
+            data_type: info.block.data_type,
+            compressed_data_size: info.block.size,
+            data: Edds::read_block(&mut self.input, &self.info, index)?,
+        })
+    }
+
+    /// Reads and decodes the full resolution mip.
+    pub fn top_mipmap(&mut self) -> Result<Mipmap, EddsError> {
+        self.mipmap(self.mip_count().saturating_sub(1))
+    }
+
+    pub fn into_inner(self) -> R {
+        self.input
+    }
+}
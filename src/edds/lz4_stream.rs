@@ -0,0 +1,138 @@
+//! LZ4 framing of EDDS mips.
+//!
+//! A mip starts with its uncompressed size as `u32`, followed by blocks of up
+//! to 64 KiB uncompressed data. Each block has a 24 bit compressed size and a
+//! flag byte that is set on the last block. Blocks are compressed as one
+//! stream, so every block can reference the data of the blocks before it.
+
+use std::io::Cursor;
+
+use lzzzz::{lz4, lz4_hc};
+
+use crate::core::{errors::EddsError, read::ReadExtTrait};
+
+/// Size of the uncompressed data in each block.
+const BLOCK_SIZE: usize = 65536;
+
+/// Flag byte of the last block.
+const LAST_BLOCK: u8 = 0x80;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lz4Level {
+    /// LZ4 with the given acceleration, higher values are faster but compress worse.
+    Fast(i32),
+    /// LZ4 HC with the given compression level, from 1 to 12.
+    High(i32),
+}
+
+impl Default for Lz4Level {
+    fn default() -> Self {
+        Lz4Level::Fast(lz4::ACC_LEVEL_DEFAULT)
+    }
+}
+
+enum BlockCompressor<'a> {
+    Fast(lz4::Compressor<'a>, i32),
+    High(lz4_hc::Compressor<'a>),
+}
+
+impl BlockCompressor<'_> {
+    fn next_to_vec(&mut self, src: &[u8], dst: &mut Vec<u8>) -> Result<usize, EddsError> {
+        Ok(match self {
+            BlockCompressor::Fast(compressor, acceleration) => {
+                compressor.next_to_vec(src, dst, *acceleration)?
+            }
+            BlockCompressor::High(compressor) => compressor.next_to_vec(src, dst)?,
+        })
+    }
+}
+
+/// Compresses the data of a mip into the EDDS LZ4 framing.
+pub fn compress_lz4_mip(src: &[u8], level: Lz4Level) -> Result<Vec<u8>, EddsError> {
+    let mut compressor = match level {
+        Lz4Level::Fast(acceleration) => {
+            BlockCompressor::Fast(lz4::Compressor::new()?, acceleration)
+        }
+        Lz4Level::High(level) => {
+            let mut compressor = lz4_hc::Compressor::new()?;
+            compressor.set_compression_level(level);
+            BlockCompressor::High(compressor)
+        }
+    };
+
+    let mut output = Vec::with_capacity(src.len() / 2 + 8);
+    output.extend((src.len() as u32).to_le_bytes());
+
+    let blocks: Vec<&[u8]> = if src.is_empty() {
+        vec![src]
+    } else {
+        src.chunks(BLOCK_SIZE).collect()
+    };
+
+    for (i, block) in blocks.iter().enumerate() {
+        let mut compressed = Vec::new();
+        compressor.next_to_vec(block, &mut compressed)?;
+
+        output.extend(&(compressed.len() as u32).to_le_bytes()[..3]);
+        output.push(if i + 1 == blocks.len() { LAST_BLOCK } else { 0 });
+        output.extend(compressed);
+    }
+
+    Ok(output)
+}
+
+/// Decompresses a mip stored in the EDDS LZ4 framing.
+pub fn decompress_lz4_mip(src: &[u8]) -> Result<Vec<u8>, EddsError> {
+    let mut input = Cursor::new(src);
+    let mut lz4_stream = lz4::Decompressor::new()?;
+    let invalid = |input: &Cursor<&[u8]>, reason: &str| EddsError::InvalidLz4Stream {
+        position: input.position() as usize,
+        reason: reason.to_string(),
+    };
+
+    let uncompressed_data_size = input
+        .read_u32()
+        .map_err(|_| invalid(&input, "missing uncompressed size"))?
+        as usize;
+    // LZ4 can't expand data more than 255 times, don't trust the size beyond that.
+    let mut complete_buffer =
+        Vec::with_capacity(std::cmp::min(uncompressed_data_size, src.len() * 255));
+
+    loop {
+        let (compress_block_size, is_last_block) = match (input.read_u24(), input.read_u8()) {
+            (Ok(size), Ok(flag)) => (size as usize, flag != 0),
+            _ => return Err(invalid(&input, "truncated block header")),
+        };
+
+        let start = input.position() as usize;
+        let buf = src
+            .get(start..start + compress_block_size)
+            .ok_or_else(|| invalid(&input, "truncated block"))?;
+        input.set_position((start + compress_block_size) as u64);
+
+        let remaining = uncompressed_data_size
+            .checked_sub(complete_buffer.len())
+            .ok_or_else(|| invalid(&input, "more data than the uncompressed size"))?;
+        let block_size = if is_last_block {
+            std::cmp::min(remaining, buf.len() * 255)
+        } else {
+            std::cmp::min(BLOCK_SIZE, remaining)
+        };
+
+        let decomp = lz4_stream.next(buf, block_size)?;
+        complete_buffer.extend_from_slice(decomp);
+
+        if is_last_block {
+            break;
+        }
+    }
+
+    if complete_buffer.len() != uncompressed_data_size {
+        return Err(invalid(&input, "data doesn't match the uncompressed size"));
+    }
+    if input.position() as usize != src.len() {
+        return Err(invalid(&input, "trailing data after the last block"));
+    }
+
+    Ok(complete_buffer)
+}
@@ -0,0 +1,301 @@
+//! Mip chain generation for RGBA8 images.
+//!
+//! Every mip is resampled from the one above it with a separable filter. The
+//! chain follows the dimension rule of [`Edds`](super::Edds): each level halves
+//! width and height, rounding down, until both reach 1.
+
+use std::f32::consts::PI;
+
+use crate::core::errors::EddsError;
+
+use super::Edds;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MipFilter {
+    /// Average of the covered pixels, cheap and slightly blurry.
+    #[default]
+    Box,
+    /// Kaiser windowed sinc, sharper than box with little ringing.
+    Kaiser,
+    /// Lanczos with 3 lobes, the sharpest of the three.
+    Lanczos,
+}
+
+impl MipFilter {
+    /// Radius of the filter in pixels of the smaller mip.
+    fn support(&self) -> f32 {
+        match self {
+            MipFilter::Box => 0.5,
+            MipFilter::Kaiser | MipFilter::Lanczos => 3.0,
+        }
+    }
+
+    fn weight(&self, x: f32) -> f32 {
+        let x = x.abs();
+        if x > self.support() {
+            return 0.0;
+        }
+
+        match self {
+            MipFilter::Box => 1.0,
+            MipFilter::Kaiser => {
+                const ALPHA: f32 = 4.0;
+                let ratio = x / self.support();
+                sinc(x) * bessel_i0(ALPHA * (1.0 - ratio * ratio).sqrt()) / bessel_i0(ALPHA)
+            }
+            MipFilter::Lanczos => sinc(x) * sinc(x / self.support()),
+        }
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-6 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+/// Modified Bessel function of the first kind, order 0.
+fn bessel_i0(x: f32) -> f32 {
+    let (mut sum, mut term, mut k) = (1.0, 1.0, 1.0);
+    while term > sum * 1e-8 {
+        term *= (x / (2.0 * k)) * (x / (2.0 * k));
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+/// Options for mip generation, start from [`MipmapOptions::default`] and set
+/// the fields that differ.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct MipmapOptions {
+    pub filter: MipFilter,
+    /// Alpha test threshold in `0.0..=1.0`. When set, the alpha of every mip is
+    /// scaled so the same fraction of pixels passes the test as in the base
+    /// image, which keeps foliage and fences from thinning out in the distance.
+    pub alpha_coverage: Option<f32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MipLevel {
+    pub width: usize,
+    pub height: usize,
+    pub rgba: Vec<u8>,
+}
+
+/// Generates the full mip chain of a `width` x `height` RGBA8 image, largest
+/// mip first. The first level is the image itself.
+///
+/// With `srgb` set the colour channels are filtered in linear light, alpha is
+/// always filtered as is.
+pub fn generate_mipmaps(
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    options: &MipmapOptions,
+    srgb: bool,
+) -> Result<Vec<MipLevel>, EddsError> {
+    check_rgba8(rgba, width, height)?;
+
+    let to_float = |value: u8, channel: usize| {
+        if srgb && channel < 3 {
+            srgb_to_linear(value as f32 / 255.0)
+        } else {
+            value as f32 / 255.0
+        }
+    };
+    let to_u8 = |value: f32, channel: usize| {
+        let value = value.clamp(0.0, 1.0);
+        let value = if srgb && channel < 3 {
+            linear_to_srgb(value)
+        } else {
+            value
+        };
+        (value * 255.0).round() as u8
+    };
+
+    let mut levels = vec![MipLevel {
+        width,
+        height,
+        rgba: rgba.to_vec(),
+    }];
+
+    let mut pixels: Vec<[f32; 4]> = rgba
+        .chunks_exact(4)
+        .map(|pixel| std::array::from_fn(|c| to_float(pixel[c], c)))
+        .collect();
+    let coverage_target = options
+        .alpha_coverage
+        .map(|threshold| (threshold, alpha_coverage(&pixels, threshold, 1.0)));
+
+    let mip_count = usize::BITS - std::cmp::max(width, height).leading_zeros();
+    let (mut src_width, mut src_height) = (width, height);
+
+    for index in 2..=mip_count {
+        let mip_width = Edds::get_dim_for_index(width as u32, index);
+        let mip_height = Edds::get_dim_for_index(height as u32, index);
+        pixels = resample(
+            &pixels,
+            src_width,
+            src_height,
+            mip_width,
+            mip_height,
+            options.filter,
+        );
+        (src_width, src_height) = (mip_width, mip_height);
+
+        let alpha_scale = coverage_target.map_or(1.0, |(threshold, target)| {
+            alpha_scale_for_coverage(&pixels, threshold, target)
+        });
+
+        let rgba = pixels
+            .iter()
+            .flat_map(|pixel| {
+                [
+                    to_u8(pixel[0], 0),
+                    to_u8(pixel[1], 1),
+                    to_u8(pixel[2], 2),
+                    to_u8(pixel[3] * alpha_scale, 3),
+                ]
+            })
+            .collect();
+        levels.push(MipLevel {
+            width: mip_width,
+            height: mip_height,
+            rgba,
+        });
+    }
+
+    Ok(levels)
+}
+
+/// Checks that `rgba` holds the pixels of a `width` x `height` RGBA8 image
+/// with at least one pixel.
+pub(crate) fn check_rgba8(rgba: &[u8], width: usize, height: usize) -> Result<(), EddsError> {
+    if width == 0 || height == 0 {
+        return Err(EddsError::InvalidImageSize(width, height));
+    }
+    let size = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .ok_or(EddsError::ImageTooLarge(width, height))?;
+    if rgba.len() != size {
+        return Err(EddsError::PixelDataSizeMismatch(size, rgba.len()));
+    }
+
+    Ok(())
+}
+
+/// Taps of every destination pixel along one axis, with normalized weights.
+fn filter_taps(src_size: usize, dst_size: usize, filter: MipFilter) -> Vec<Vec<(usize, f32)>> {
+    let scale = src_size as f32 / dst_size as f32;
+    let radius = filter.support() * scale.max(1.0);
+
+    (0..dst_size)
+        .map(|dst| {
+            let center = (dst as f32 + 0.5) * scale - 0.5;
+            let first = (center - radius).floor() as isize;
+            let last = (center + radius).ceil() as isize;
+
+            let mut taps: Vec<(usize, f32)> = (first..=last)
+                .map(|src| {
+                    let weight = filter.weight((src as f32 - center) / scale.max(1.0));
+                    (src.clamp(0, src_size as isize - 1) as usize, weight)
+                })
+                .filter(|(_, weight)| *weight != 0.0)
+                .collect();
+
+            let total: f32 = taps.iter().map(|(_, weight)| weight).sum();
+            for (_, weight) in taps.iter_mut() {
+                *weight /= total;
+            }
+            taps
+        })
+        .collect()
+}
+
+fn resample(
+    src: &[[f32; 4]],
+    src_width: usize,
+    src_height: usize,
+    dst_width: usize,
+    dst_height: usize,
+    filter: MipFilter,
+) -> Vec<[f32; 4]> {
+    let apply = |taps: &[(usize, f32)], sample: &dyn Fn(usize) -> [f32; 4]| {
+        let mut value = [0.0; 4];
+        for &(src, weight) in taps {
+            for (value, sample) in value.iter_mut().zip(sample(src)) {
+                *value += sample * weight;
+            }
+        }
+        value
+    };
+
+    let horizontal_taps = filter_taps(src_width, dst_width, filter);
+    let mut horizontal = Vec::with_capacity(dst_width * src_height);
+    for y in 0..src_height {
+        for taps in &horizontal_taps {
+            horizontal.push(apply(taps, &|x| src[y * src_width + x]));
+        }
+    }
+
+    let vertical_taps = filter_taps(src_height, dst_height, filter);
+    let mut output = Vec::with_capacity(dst_width * dst_height);
+    for taps in &vertical_taps {
+        for x in 0..dst_width {
+            output.push(apply(taps, &|y| horizontal[y * dst_width + x]));
+        }
+    }
+
+    output
+}
+
+/// Fraction of pixels whose alpha, scaled and rounded to 8 bits, passes `threshold`.
+fn alpha_coverage(pixels: &[[f32; 4]], threshold: f32, scale: f32) -> f32 {
+    let covered = pixels
+        .iter()
+        .filter(|pixel| ((pixel[3] * scale).clamp(0.0, 1.0) * 255.0).round() / 255.0 > threshold)
+        .count();
+    covered as f32 / pixels.len() as f32
+}
+
+/// Finds the alpha scale that brings the coverage of `pixels` closest to `target`.
+fn alpha_scale_for_coverage(pixels: &[[f32; 4]], threshold: f32, target: f32) -> f32 {
+    let (mut low, mut high) = (0.0, 8.0);
+    let mut best = (1.0, (alpha_coverage(pixels, threshold, 1.0) - target).abs());
+
+    for _ in 0..16 {
+        let scale = (low + high) / 2.0;
+        let coverage = alpha_coverage(pixels, threshold, scale);
+        if (coverage - target).abs() < best.1 {
+            best = (scale, (coverage - target).abs());
+        }
+        if coverage < target {
+            low = scale;
+        } else {
+            high = scale;
+        }
+    }
+
+    best.0
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
@@ -1,6 +1,40 @@
+mod bc7_encoder;
+mod bcn;
+mod dds;
 mod dds_header;
 #[allow(clippy::module_inception)]
 mod edds;
+mod encode;
+mod format;
+#[cfg(feature = "image")]
+mod image_decoder;
+mod info;
+mod layout;
+mod lazy;
+mod lz4_stream;
+mod mip_chain;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod raw;
+mod repack;
+mod uncompressed;
+mod validation;
+mod writer;
 
+pub use crate::core::errors::{EddsError, HeaderDiagnostic, HeaderField};
+
+pub use self::dds::*;
 pub use self::dds_header::*;
 pub use self::edds::*;
+pub use self::format::*;
+#[cfg(feature = "image")]
+pub use self::image_decoder::*;
+pub use self::info::*;
+pub use self::layout::*;
+pub use self::lazy::*;
+pub use self::lz4_stream::*;
+pub use self::mip_chain::*;
+pub use self::raw::*;
+pub use self::repack::*;
+pub use self::validation::*;
+pub use self::writer::*;
@@ -0,0 +1,117 @@
+//! Multi-threaded decoding, available with the `rayon` feature.
+//!
+//! Mips and surfaces are decoded concurrently and large block compressed
+//! surfaces are split into bands of block rows. The result is the same as the
+//! single threaded decoders produce.
+
+use std::io::{BufRead, Seek};
+
+use rayon::prelude::*;
+
+use crate::core::errors::EddsError;
+
+use super::{
+    raw::concat_pixel_data, DdsHeader, DecodeOptions, DecodedFormat, Edds, Mipmap, PixelData,
+    RawEdds, RawMipmap, StorageFormat, TextureLayout,
+};
+
+/// Number of blocks decoded by a single task, surfaces with fewer blocks are
+/// decoded in one go.
+const BLOCKS_PER_BAND: usize = 1024;
+
+impl Edds {
+    /// Like [`Edds::from`] but decodes on the rayon thread pool.
+    pub fn par_from<I>(input: &mut I) -> Result<Edds, EddsError>
+    where
+        I: Seek + BufRead,
+    {
+        Edds::par_from_with_options(input, &DecodeOptions::default())
+    }
+
+    /// Like [`Edds::from_with_options`] but decodes on the rayon thread pool.
+    pub fn par_from_with_options<I>(
+        input: &mut I,
+        options: &DecodeOptions,
+    ) -> Result<Edds, EddsError>
+    where
+        I: Seek + BufRead,
+    {
+        RawEdds::from_with_mode(input, options.header_mode)?.par_decode(options)
+    }
+}
+
+impl RawEdds {
+    /// Like [`RawEdds::decode`] but decodes on the rayon thread pool.
+    pub fn par_decode(&self, options: &DecodeOptions) -> Result<Edds, EddsError> {
+        let format = DecodedFormat::from_header(&self.header)?;
+        let mipmaps = self
+            .mipmaps
+            .par_iter()
+            .map(|raw| raw.par_decode(&self.header, options))
+            .collect::<Result<Vec<_>, EddsError>>()?;
+
+        Ok(Edds {
+            header: self.header.clone(),
+            blocks: self.mipmaps.iter().map(RawMipmap::block).collect(),
+            format,
+            layout: TextureLayout::from_header(&self.header),
+            mipmaps,
+        })
+    }
+}
+
+impl RawMipmap {
+    /// Like [`RawMipmap::decode`] but decodes on the rayon thread pool.
+    pub fn par_decode(
+        &self,
+        header: &DdsHeader,
+        options: &DecodeOptions,
+    ) -> Result<Mipmap, EddsError> {
+        let surfaces = (0..TextureLayout::from_header(header).surface_count())
+            .into_par_iter()
+            .map(|surface| self.par_decode_surface(surface, header, options))
+            .collect::<Result<Vec<_>, EddsError>>()?;
+
+        self.decoded(surfaces, header)
+    }
+
+    /// Decodes `surface` in bands of block rows. Volumes, formats without
+    /// blocks and truncated data take the single threaded path.
+    fn par_decode_surface(
+        &self,
+        surface: usize,
+        header: &DdsHeader,
+        options: &DecodeOptions,
+    ) -> Result<PixelData, EddsError> {
+        let data = self.surface_data(header, surface);
+        let format = StorageFormat::from_header(header);
+
+        let (band_height, band_size) = match format.block_layout() {
+            Some((block_width, block_size)) if self.depth <= 1 => {
+                let row_blocks = self.width.div_ceil(block_width);
+                let band_rows = std::cmp::max(BLOCKS_PER_BAND / row_blocks, 1);
+                (band_rows * block_width, band_rows * row_blocks * block_size)
+            }
+            _ => return self.decode_surface(surface, header, options),
+        };
+
+        let complete = format
+            .data_size(self.width, self.height)
+            .is_ok_and(|size| data.len() >= size);
+        if self.height <= band_height || !complete {
+            return self.decode_surface(surface, header, options);
+        }
+
+        let bands = data
+            .par_chunks(band_size)
+            .take(self.height.div_ceil(band_height))
+            .enumerate()
+            .map(|(band, data)| {
+                let height = std::cmp::min(band_height, self.height - band * band_height);
+                Edds::decode_data(data, self.width, height, header, options)
+            })
+            .collect::<Result<Vec<_>, EddsError>>()?;
+
+        Ok(concat_pixel_data(bands))
+    }
+}
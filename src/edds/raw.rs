@@ -0,0 +1,197 @@
+use std::io::{BufRead, Seek};
+
+use crate::core::errors::EddsError;
+
+use super::{
+    DdsHeader, DecodeOptions, DecodedFormat, Edds, EddsInfo, HeaderMode, Mipmap, MipmapBlock,
+    MipmapType, PixelData, StorageFormat, TextureLayout,
+};
+
+/// EDDS file with LZ4 removed but the pixel data still in its stored format,
+/// e.g. BCn blocks that can be uploaded to a GPU or written to a DDS file.
+#[derive(Debug, Clone)]
+pub struct RawEdds {
+    pub header: DdsHeader,
+    /// Mips in file order, smallest mip first.
+    pub mipmaps: Vec<RawMipmap>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RawMipmap {
+    pub width: usize,
+    pub height: usize,
+    /// Number of depth slices, 1 unless the texture is a volume.
+    pub depth: usize,
+    pub data_type: MipmapType,
+    pub compressed_data_size: u32,
+    /// Stored data of every surface of the mip, see [`TextureLayout`].
+    pub data: Vec<u8>,
+}
+
+impl RawEdds {
+    pub fn from<I>(input: &mut I) -> Result<RawEdds, EddsError>
+    where
+        I: Seek + BufRead,
+    {
+        RawEdds::from_with_mode(input, HeaderMode::Lenient)
+    }
+
+    pub fn from_with_mode<I>(input: &mut I, mode: HeaderMode) -> Result<RawEdds, EddsError>
+    where
+        I: Seek + BufRead,
+    {
+        let info = EddsInfo::from_with_mode(input, mode)?;
+
+        let mut raw_mipmaps = Vec::with_capacity(info.mip_count());
+        for (index, mipmap) in info.mipmaps.iter().enumerate() {
+            raw_mipmaps.push(RawMipmap {
+                width: mipmap.width,
+                height: mipmap.height,
+                depth: mipmap.depth,
+                data_type: mipmap.block.data_type,
+                compressed_data_size: mipmap.block.size,
+                data: Edds::read_block(input, &info, index)?,
+            });
+        }
+
+        Ok(RawEdds {
+            header: info.header,
+            mipmaps: raw_mipmaps,
+        })
+    }
+
+    /// Decodes every surface of every mip to pixels.
+    pub fn decode(&self, options: &DecodeOptions) -> Result<Edds, EddsError> {
+        let format = DecodedFormat::from_header(&self.header)?;
+        let mipmaps = self
+            .mipmaps
+            .iter()
+            .map(|raw| raw.decode(&self.header, options))
+            .collect::<Result<Vec<_>, EddsError>>()?;
+
+        Ok(Edds {
+            header: self.header.clone(),
+            blocks: self.mipmaps.iter().map(RawMipmap::block).collect(),
+            format,
+            layout: TextureLayout::from_header(&self.header),
+            mipmaps,
+        })
+    }
+
+    /// Decodes the mip at `index` to pixels.
+    pub fn decode_mipmap(
+        &self,
+        index: usize,
+        options: &DecodeOptions,
+    ) -> Result<Mipmap, EddsError> {
+        let raw = self
+            .mipmaps
+            .get(index)
+            .ok_or(EddsError::MipmapIndexOutOfRange(index, self.mipmaps.len()))?;
+
+        raw.decode(&self.header, options)
+    }
+}
+
+impl RawMipmap {
+    /// Decodes every surface of the mip to pixels, `header` is the header of
+    /// the file the mip was read from.
+    pub fn decode(&self, header: &DdsHeader, options: &DecodeOptions) -> Result<Mipmap, EddsError> {
+        let surfaces = (0..TextureLayout::from_header(header).surface_count())
+            .map(|surface| self.decode_surface(surface, header, options))
+            .collect::<Result<Vec<_>, EddsError>>()?;
+
+        self.decoded(surfaces, header)
+    }
+
+    /// Size of a single depth slice, for formats that can't be decoded and sizes
+    /// that overflow the data is split evenly between the slices of every surface.
+    fn slice_size(&self, header: &DdsHeader) -> usize {
+        StorageFormat::from_header(header)
+            .data_size(self.width, self.height)
+            .unwrap_or_else(|_| {
+                let slice_count = TextureLayout::from_header(header).surface_count()
+                    * std::cmp::max(self.depth, 1);
+                self.data.len() / slice_count
+            })
+    }
+
+    /// Stored data of `surface`, cut short if the mip is truncated.
+    pub(crate) fn surface_data(&self, header: &DdsHeader, surface: usize) -> &[u8] {
+        let surface_size = self
+            .slice_size(header)
+            .saturating_mul(std::cmp::max(self.depth, 1));
+        let start = std::cmp::min(surface.saturating_mul(surface_size), self.data.len());
+        let end = std::cmp::min(start.saturating_add(surface_size), self.data.len());
+        &self.data[start..end]
+    }
+
+    /// Decodes the stored data of `surface`, depth slice by depth slice.
+    pub(crate) fn decode_surface(
+        &self,
+        surface: usize,
+        header: &DdsHeader,
+        options: &DecodeOptions,
+    ) -> Result<PixelData, EddsError> {
+        let data = self.surface_data(header, surface);
+        let depth = std::cmp::max(self.depth, 1);
+        if depth == 1 {
+            Edds::decode_data(data, self.width, self.height, header, options)
+        } else {
+            let slice_size = self.slice_size(header);
+            let slices = (0..depth)
+                .map(|slice| {
+                    let start = std::cmp::min(slice * slice_size, data.len());
+                    let end = std::cmp::min(start + slice_size, data.len());
+                    Edds::decode_data(&data[start..end], self.width, self.height, header, options)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(concat_pixel_data(slices))
+        }
+    }
+
+    /// Wraps the decoded pixel data of every surface in a [`Mipmap`].
+    pub(crate) fn decoded(
+        &self,
+        surfaces: Vec<PixelData>,
+        header: &DdsHeader,
+    ) -> Result<Mipmap, EddsError> {
+        Ok(Mipmap {
+            width: self.width,
+            height: self.height,
+            data_type: self.data_type,
+            compressed_data_size: self.compressed_data_size,
+            depth: std::cmp::max(self.depth, 1),
+            format: DecodedFormat::from_header(header)?,
+            surfaces,
+        })
+    }
+
+    pub fn block(&self) -> MipmapBlock {
+        MipmapBlock {
+            data_type: self.data_type,
+            size: self.compressed_data_size,
+        }
+    }
+}
+
+/// Joins the decoded depth slices of a volume mip or the row bands of a
+/// surface, all parts share a type.
+pub(crate) fn concat_pixel_data(parts: Vec<PixelData>) -> PixelData {
+    let mut parts = parts.into_iter();
+    let mut output = match parts.next() {
+        Some(first) => first,
+        None => return PixelData::U8(Vec::new()),
+    };
+
+    for part in parts {
+        match (&mut output, part) {
+            (PixelData::U8(output), PixelData::U8(part)) => output.extend(part),
+            (PixelData::U16(output), PixelData::U16(part)) => output.extend(part),
+            (PixelData::F32(output), PixelData::F32(part)) => output.extend(part),
+            _ => unreachable!("parts of a mip decode to the same type"),
+        }
+    }
+
+    output
+}
@@ -0,0 +1,44 @@
+//! Conversion between EDDS and DDS files without decoding the pixel data.
+
+use std::io::{BufRead, Seek, Write};
+
+use crate::core::errors::EddsError;
+
+use super::{Compression, Lz4Level, RawDds, RawEdds};
+
+/// Options for repacking, start from [`RepackOptions::default`] and set the
+/// fields that differ.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct RepackOptions {
+    /// With [`Compression::Lz4`] every mip is compressed, but mips that LZ4
+    /// doesn't make smaller are stored as `COPY`, see [`RawEdds::write`].
+    pub compression: Compression,
+    pub level: Lz4Level,
+}
+
+/// Repacks an EDDS file into a DDS file.
+///
+/// The `COPY`/`LZ4 ` wrapping is removed and the mips are written in DDS order.
+pub fn edds_to_dds<I, W>(input: &mut I, output: &mut W) -> Result<(), EddsError>
+where
+    I: Seek + BufRead,
+    W: Write,
+{
+    RawDds::from_edds(RawEdds::from(input)?).write(output)
+}
+
+/// Repacks a DDS file into an EDDS file, compressing the mips as set in `options`.
+pub fn dds_to_edds<I, W>(
+    input: &mut I,
+    output: &mut W,
+    options: &RepackOptions,
+) -> Result<(), EddsError>
+where
+    I: Seek + BufRead,
+    W: Write,
+{
+    RawDds::from(input)?
+        .into_edds(options.compression)
+        .write_with_level(output, options.level)
+}
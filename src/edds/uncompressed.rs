@@ -0,0 +1,126 @@
+//! Decoders for uncompressed pixel formats.
+//!
+//! Single channel formats decode to one channel, HDR formats without alpha
+//! decode to RGB and everything else decodes to RGBA. Missing colour channels
+//! are zero and a missing alpha channel is fully opaque.
+
+use super::bcn::half_to_f32;
+
+fn read_pixel(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |value, &byte| (value << 8) | byte as u32)
+}
+
+/// Extracts the channel selected by `mask` and rescales it to `0..=max`.
+fn extract_channel(pixel: u32, mask: u32, max: u32) -> u32 {
+    let shift = mask.trailing_zeros();
+    let channel_max = (mask >> shift) as u64;
+    let value = ((pixel & mask) >> shift) as u64;
+    ((value * max as u64 + channel_max / 2) / channel_max) as u32
+}
+
+fn decode_masked<T>(
+    src: &[u8],
+    bytes_per_pixel: usize,
+    masks: [u32; 4],
+    max: u32,
+    convert: fn(u32) -> T,
+) -> Vec<T> {
+    let mut output = Vec::with_capacity(src.len() / bytes_per_pixel * 4);
+
+    for bytes in src.chunks_exact(bytes_per_pixel) {
+        let pixel = read_pixel(bytes);
+        for (channel, &mask) in masks.iter().enumerate() {
+            let value = match (mask, channel) {
+                (0, 3) => max,
+                (0, _) => 0,
+                (mask, _) => extract_channel(pixel, mask, max),
+            };
+            output.push(convert(value));
+        }
+    }
+
+    output
+}
+
+/// Decodes pixels described by `r`, `g`, `b` and `a` bit masks to RGBA8.
+pub(crate) fn decode_masked_u8(src: &[u8], bytes_per_pixel: usize, masks: [u32; 4]) -> Vec<u8> {
+    decode_masked(src, bytes_per_pixel, masks, u8::MAX as u32, |v| v as u8)
+}
+
+/// Decodes pixels described by `r`, `g`, `b` and `a` bit masks to RGBA16.
+pub(crate) fn decode_masked_u16(src: &[u8], bytes_per_pixel: usize, masks: [u32; 4]) -> Vec<u16> {
+    decode_masked(src, bytes_per_pixel, masks, u16::MAX as u32, |v| v as u16)
+}
+
+/// Remaps signed 8 bit values from `[-127, 127]` to `[0, 255]`.
+pub(crate) fn snorm8_to_unorm8(src: &[u8]) -> Vec<u8> {
+    src.iter()
+        .map(|&value| {
+            let value = (value as i8).max(-127) as f32 / 127.0;
+            ((value * 0.5 + 0.5) * 255.0).round() as u8
+        })
+        .collect()
+}
+
+pub(crate) fn decode_u16(src: &[u8]) -> Vec<u16> {
+    src.chunks_exact(2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .collect()
+}
+
+pub(crate) fn decode_f16(src: &[u8]) -> Vec<f32> {
+    src.chunks_exact(2)
+        .map(|bytes| half_to_f32(u16::from_le_bytes([bytes[0], bytes[1]])))
+        .collect()
+}
+
+pub(crate) fn decode_f32(src: &[u8]) -> Vec<f32> {
+    src.chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect()
+}
+
+/// Unsigned float with a 5 bit exponent, as used by `R11G11B10_FLOAT`.
+fn small_float_to_f32(value: u32, mantissa_bits: u32) -> f32 {
+    let exponent = (value >> mantissa_bits) as i32;
+    let mantissa = (value & ((1 << mantissa_bits) - 1)) as f32 / (1 << mantissa_bits) as f32;
+
+    match exponent {
+        0 => mantissa * 2_f32.powi(-14),
+        31 if mantissa == 0.0 => f32::INFINITY,
+        31 => f32::NAN,
+        _ => (1.0 + mantissa) * 2_f32.powi(exponent - 15),
+    }
+}
+
+/// Decodes `R11G11B10_FLOAT` to RGB32F.
+pub(crate) fn decode_r11g11b10_float(src: &[u8]) -> Vec<f32> {
+    let mut output = Vec::with_capacity(src.len() / 4 * 3);
+
+    for bytes in src.chunks_exact(4) {
+        let pixel = read_pixel(bytes);
+        output.push(small_float_to_f32(pixel & 0x7FF, 6));
+        output.push(small_float_to_f32((pixel >> 11) & 0x7FF, 6));
+        output.push(small_float_to_f32(pixel >> 22, 5));
+    }
+
+    output
+}
+
+/// Decodes `R9G9B9E5_SHAREDEXP` to RGB32F.
+pub(crate) fn decode_r9g9b9e5(src: &[u8]) -> Vec<f32> {
+    let mut output = Vec::with_capacity(src.len() / 4 * 3);
+
+    for bytes in src.chunks_exact(4) {
+        let pixel = read_pixel(bytes);
+        let scale = 2_f32.powi((pixel >> 27) as i32 - 15 - 9);
+        for channel in 0..3 {
+            output.push(((pixel >> (channel * 9)) & 0x1FF) as f32 * scale);
+        }
+    }
+
+    output
+}
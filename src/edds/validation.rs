@@ -0,0 +1,89 @@
+use enumflags2::BitFlags;
+
+use crate::core::errors::{HeaderDiagnostic, HeaderField};
+
+use super::{DdsCapsFlags, DdsHeader, DdsHeaderFlags, StorageFormat, TextureKind, TextureLayout};
+
+/// How headers are checked while reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeaderMode {
+    /// Accept any header that can be decoded, problems are only reported by
+    /// [`DdsHeader::diagnostics`].
+    #[default]
+    Lenient,
+    /// Reject headers with any diagnostic.
+    Strict,
+}
+
+impl DdsHeader {
+    /// Checks the header for unknown bits and inconsistent fields.
+    pub fn diagnostics(&self) -> Vec<HeaderDiagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for (field, bits) in [
+            (HeaderField::Flags, self.unknown_flags),
+            (HeaderField::PixelFormatFlags, self.ddspf.unknown_flags),
+            (HeaderField::Caps, self.unknown_caps),
+            (HeaderField::Caps2, self.unknown_caps2),
+        ] {
+            if bits != 0 {
+                diagnostics.push(HeaderDiagnostic::UnknownBits { field, bits });
+            }
+        }
+
+        let mut required = DdsHeaderFlags::DDSD_CAPS
+            | DdsHeaderFlags::DDSD_HEIGHT
+            | DdsHeaderFlags::DDSD_WIDTH
+            | DdsHeaderFlags::DDSD_PIXELFORMAT;
+        if self.mip_map_count > 1 {
+            required |= DdsHeaderFlags::DDSD_MIPMAPCOUNT;
+        }
+        if TextureLayout::from_header(self).kind == TextureKind::Volume {
+            required |= DdsHeaderFlags::DDSD_DEPTH;
+        }
+        diagnostics.extend(
+            (required & !self.flags)
+                .iter()
+                .map(|flag| HeaderDiagnostic::MissingFlag(flag as u32)),
+        );
+
+        if let Some(expected) = self.expected_pitch_or_linear_size() {
+            if expected != self.pitch_or_linear_size {
+                diagnostics.push(HeaderDiagnostic::PitchOrLinearSize {
+                    expected,
+                    actual: self.pitch_or_linear_size,
+                });
+            }
+        }
+
+        let mipmap_caps: BitFlags<DdsCapsFlags> =
+            DdsCapsFlags::DDSCAPS_COMPLEX | DdsCapsFlags::DDSCAPS_MIPMAP;
+        if self.mip_map_count > 1 && !self.caps.contains(mipmap_caps) {
+            diagnostics.push(HeaderDiagnostic::MissingMipmapCaps {
+                mip_map_count: self.mip_map_count,
+            });
+        }
+
+        if self.ddspf.size != 32 {
+            diagnostics.push(HeaderDiagnostic::PixelFormatSize(self.ddspf.size));
+        }
+
+        diagnostics
+    }
+
+    /// Size of the largest mip for `DDSD_LINEARSIZE` or its row pitch for
+    /// `DDSD_PITCH`, `None` if neither flag is set or the format is unknown.
+    fn expected_pitch_or_linear_size(&self) -> Option<u32> {
+        let format = StorageFormat::from_header(self);
+        let (width, height) = (self.width as usize, self.height as usize);
+
+        if self.flags.contains(DdsHeaderFlags::DDSD_LINEARSIZE) {
+            format.data_size(width, height).ok().map(|size| size as u32)
+        } else if self.flags.contains(DdsHeaderFlags::DDSD_PITCH) {
+            let (block_width, block_size) = format.block_layout()?;
+            Some((width.div_ceil(block_width) * block_size) as u32)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::{borrow::Cow, io::Write};
+
+use crate::core::errors::EddsError;
+
+use super::{
+    encode::encode_rgba8,
+    lz4_stream::{compress_lz4_mip, Lz4Level},
+    mip_chain::{check_rgba8, generate_mipmaps, MipLevel, MipmapOptions},
+    DdsHeader, DdsHeaderDX10, DdsPixelFormat, DxgiFormat, FourCCEnum, MipmapType, RawEdds,
+    RawMipmap,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    /// Store every mip as `COPY`.
+    None,
+    /// Store every mip as `LZ4 `, unless LZ4 doesn't make it smaller.
+    #[default]
+    Lz4,
+}
+
+/// Trade-off between encoding speed and quality of the block compressed formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EncodeQuality {
+    Fast,
+    #[default]
+    Normal,
+    Slow,
+}
+
+/// Options for encoding, start from [`EncodeOptions::default`] and set the
+/// fields that differ.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct EncodeOptions {
+    /// BC1, BC3, BC4, BC5, BC7 or one of the 8 bit RGBA formats.
+    pub format: DxgiFormat,
+    pub quality: EncodeQuality,
+    /// Write a legacy header instead of a DX10 header, only possible for
+    /// formats that have a legacy equivalent. BC1 to BC5 use their FourCC,
+    /// BC7 and the sRGB formats always need a DX10 header.
+    pub legacy_header: bool,
+    /// Generate the full mip chain instead of storing only the source image.
+    /// sRGB formats are filtered in linear light.
+    pub mipmaps: Option<MipmapOptions>,
+    pub compression: Compression,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self {
+            format: DxgiFormat::DXGI_FORMAT_B8G8R8A8_UNORM,
+            quality: EncodeQuality::default(),
+            legacy_header: false,
+            mipmaps: Some(MipmapOptions::default()),
+            compression: Compression::default(),
+        }
+    }
+}
+
+impl RawEdds {
+    /// Encodes a `width` x `height` RGBA8 image.
+    pub fn from_rgba8(
+        width: usize,
+        height: usize,
+        rgba: &[u8],
+        options: &EncodeOptions,
+    ) -> Result<RawEdds, EddsError> {
+        check_rgba8(rgba, width, height)?;
+
+        let levels = match &options.mipmaps {
+            Some(mipmap_options) => generate_mipmaps(
+                rgba,
+                width,
+                height,
+                mipmap_options,
+                options.format.is_srgb(),
+            )?,
+            None => vec![MipLevel {
+                width,
+                height,
+                rgba: rgba.to_vec(),
+            }],
+        };
+
+        let data_type = match options.compression {
+            Compression::None => MipmapType::COPY,
+            Compression::Lz4 => MipmapType::LZ4,
+        };
+
+        let mut mipmaps = Vec::with_capacity(levels.len());
+        for level in levels.into_iter().rev() {
+            let data = encode_rgba8(
+                &level.rgba,
+                level.width,
+                level.height,
+                options.format,
+                options.quality,
+            )?;
+            mipmaps.push(RawMipmap {
+                width: level.width,
+                height: level.height,
+                data_type,
+                compressed_data_size: data.len() as u32,
+                depth: 1,
+                data,
+            });
+        }
+
+        let (ddspf, dx10_header) = if options.legacy_header {
+            let ddspf = legacy_pixel_format(options.format).ok_or_else(|| {
+                EddsError::UnsupportedEncodeFormat(format!(
+                    "{:?} with a legacy header",
+                    options.format
+                ))
+            })?;
+            (ddspf, None)
+        } else {
+            (
+                DdsPixelFormat::from_four_cc(FourCCEnum::DX10),
+                Some(DdsHeaderDX10::new_texture_2d(options.format)),
+            )
+        };
+
+        Ok(RawEdds {
+            header: DdsHeader::new(
+                width as u32,
+                height as u32,
+                mipmaps.len() as u32,
+                ddspf,
+                dx10_header,
+            ),
+            mipmaps,
+        })
+    }
+
+    /// Writes the headers, the mip table and the mips, compressing the mips
+    /// stored as `LZ4 ` with the default level. `LZ4 ` mips that don't get
+    /// smaller are written as `COPY`, like Enfusion does for the smallest mips.
+    ///
+    /// `compressed_data_size` of the mips is ignored, the table is built from
+    /// the data that is written.
+    pub fn write<W>(&self, output: &mut W) -> Result<(), EddsError>
+    where
+        W: Write,
+    {
+        self.write_with_level(output, Lz4Level::default())
+    }
+
+    /// Like [`RawEdds::write`] but compresses `LZ4 ` mips with `level`.
+    pub fn write_with_level<W>(&self, output: &mut W, level: Lz4Level) -> Result<(), EddsError>
+    where
+        W: Write,
+    {
+        let stored = self
+            .mipmaps
+            .iter()
+            .map(|mipmap| {
+                if mipmap.data_type == MipmapType::LZ4 {
+                    let compressed = compress_lz4_mip(&mipmap.data, level)?;
+                    if compressed.len() < mipmap.data.len() {
+                        return Ok((MipmapType::LZ4, Cow::Owned(compressed)));
+                    }
+                }
+
+                Ok((MipmapType::COPY, Cow::Borrowed(mipmap.data.as_slice())))
+            })
+            .collect::<Result<Vec<_>, EddsError>>()?;
+
+        output.write_all(&self.header.to_stream_bytes()?)?;
+
+        for (data_type, data) in &stored {
+            let tag = match data_type {
+                MipmapType::COPY => b"COPY",
+                MipmapType::LZ4 => b"LZ4 ",
+            };
+            output.write_all(tag)?;
+            output.write_all(&(data.len() as u32).to_le_bytes())?;
+        }
+
+        for (_, data) in &stored {
+            output.write_all(data)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Legacy pixel format that stores the same data as `format`, block compressed
+/// formats use their FourCC.
+fn legacy_pixel_format(format: DxgiFormat) -> Option<DdsPixelFormat> {
+    let four_cc = DdsPixelFormat::from_four_cc;
+    match format {
+        DxgiFormat::DXGI_FORMAT_R8G8B8A8_UNORM => Some(DdsPixelFormat::from_masks(
+            32, 0xFF, 0xFF00, 0xFF0000, 0xFF000000,
+        )),
+        DxgiFormat::DXGI_FORMAT_B8G8R8A8_UNORM => Some(DdsPixelFormat::from_masks(
+            32, 0xFF0000, 0xFF00, 0xFF, 0xFF000000,
+        )),
+        DxgiFormat::DXGI_FORMAT_B8G8R8X8_UNORM => {
+            Some(DdsPixelFormat::from_masks(32, 0xFF0000, 0xFF00, 0xFF, 0))
+        }
+        DxgiFormat::DXGI_FORMAT_BC1_UNORM => Some(four_cc(FourCCEnum::DXT1)),
+        DxgiFormat::DXGI_FORMAT_BC2_UNORM => Some(four_cc(FourCCEnum::DXT3)),
+        DxgiFormat::DXGI_FORMAT_BC3_UNORM => Some(four_cc(FourCCEnum::DXT5)),
+        DxgiFormat::DXGI_FORMAT_BC4_UNORM => Some(four_cc(FourCCEnum::ATI1)),
+        DxgiFormat::DXGI_FORMAT_BC4_SNORM => Some(four_cc(FourCCEnum::BC4S)),
+        DxgiFormat::DXGI_FORMAT_BC5_UNORM => Some(four_cc(FourCCEnum::ATI2)),
+        DxgiFormat::DXGI_FORMAT_BC5_SNORM => Some(four_cc(FourCCEnum::BC5S)),
+        _ => None,
+    }
+}
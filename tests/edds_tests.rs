@@ -1,6 +1,16 @@
-use std::{fs::File, io::BufReader};
+use std::{
+    fs::File,
+    io::{BufReader, Cursor},
+};
 
-use eff::edds::Edds;
+use eff::edds::{
+    compress_lz4_mip, dds_to_edds, decompress_lz4_mip, edds_to_dds, generate_mipmaps, ColorSpace,
+    Compression, CubeFace, Dds, DdsHeader, DdsHeaderDX10, DdsHeaderFlags, DdsPixelFormat,
+    DecodeOptions, DxgiFormat, Edds, EddsError, EddsInfo, EncodeOptions, EncodeQuality, FourCCEnum,
+    HeaderDiagnostic, HeaderField, HeaderMode, LazyEdds, Lz4Level, MipFilter, MipLevel,
+    MipmapOptions, MipmapType, PixelData, RawDds, RawEdds, RawMipmap, RepackOptions, SampleType,
+    StorageFormat, TextureKind,
+};
 use serial_test::serial;
 
 const INPUT_PATH_PREFIX: &str = "./tests/test-data/edds_in/";
@@ -11,7 +21,7 @@ fn export_mipmaps(edds: &Edds, filename: &str, color_type: image::ColorType) {
     for (i, mipmap) in edds.mipmaps.iter().enumerate() {
         image::save_buffer(
             format!("{}{}.out.{}.png", OUTPUT_PATH_PREFIX, filename, i),
-            &mipmap.data,
+            mipmap.data().unwrap().as_u8().unwrap(),
             mipmap.width as u32,
             mipmap.height as u32,
             color_type,
@@ -20,6 +30,16 @@ fn export_mipmaps(edds: &Edds, filename: &str, color_type: image::ColorType) {
     }
 }
 
+fn top_mipmap_pixels(edds: &Edds) -> &[u8] {
+    edds.mipmaps
+        .last()
+        .unwrap()
+        .data()
+        .unwrap()
+        .as_u8()
+        .unwrap()
+}
+
 #[test]
 #[serial]
 fn edds_bc4_test() {
@@ -86,3 +106,1717 @@ fn eden_1337_supertexture_test() {
         image::ColorType::Rgba8,
     );
 }
+
+#[test]
+#[serial]
+fn edds_bc1_test() {
+    let file = File::open(format!("{}bc1_dxt1.edds", INPUT_PATH_PREFIX)).unwrap();
+    let dxt1 = Edds::from(&mut BufReader::new(file)).unwrap();
+
+    let file = File::open(format!("{}bc1_dx10.edds", INPUT_PATH_PREFIX)).unwrap();
+    let dx10 = Edds::from(&mut BufReader::new(file)).unwrap();
+
+    export_mipmaps(&dxt1, "bc1_dxt1", image::ColorType::Rgba8);
+    export_mipmaps(&dx10, "bc1_dx10", image::ColorType::Rgba8);
+
+    assert_eq!(dxt1.mipmaps.len(), 4);
+    for (a, b) in dxt1.mipmaps.iter().zip(dx10.mipmaps.iter()) {
+        assert_eq!(a.surfaces, b.surfaces);
+    }
+
+    let width = dxt1.mipmaps.last().unwrap().width;
+    let top = top_mipmap_pixels(&dxt1);
+    let pixel = |x: usize, y: usize| &top[(y * width + x) * 4..][..4];
+
+    // 4-colour block, red and blue endpoints
+    assert_eq!(pixel(0, 0), [255, 0, 0, 255]);
+    assert_eq!(pixel(1, 0), [0, 0, 255, 255]);
+    // 3-colour block, index 3 is punch-through
+    assert_eq!(pixel(4, 0), [0, 0, 255, 255]);
+    assert_eq!(pixel(5, 0), [0, 255, 0, 255]);
+    assert_eq!(pixel(7, 0)[3], 0);
+    // opaque white and fully transparent blocks
+    assert_eq!(pixel(0, 4), [255, 255, 255, 255]);
+    assert_eq!(pixel(4, 4)[3], 0);
+}
+
+#[test]
+#[serial]
+fn edds_bc2_bc3_test() {
+    let open = |name: &str| {
+        let file = File::open(format!("{}{}.edds", INPUT_PATH_PREFIX, name)).unwrap();
+        let edds = Edds::from(&mut BufReader::new(file)).unwrap();
+        export_mipmaps(&edds, name, image::ColorType::Rgba8);
+        edds
+    };
+
+    let dxt5 = open("bc3_dxt5");
+    let top = top_mipmap_pixels(&dxt5);
+    assert_eq!(
+        top[..16],
+        [255, 0, 0, 255, 255, 0, 0, 255, 255, 0, 0, 0, 255, 0, 0, 0]
+    );
+    assert_eq!(top_mipmap_pixels(&open("bc3_dx10")), top);
+
+    let dxt3 = open("bc2_dxt3");
+    let alpha: Vec<u8> = top_mipmap_pixels(&dxt3)[..16]
+        .chunks(4)
+        .map(|p| p[3])
+        .collect();
+    assert_eq!(alpha, [255, 136, 0, 68]);
+
+    // premultiplied colour is divided back out
+    assert_eq!(top_mipmap_pixels(&open("bc3_dxt4"))[..4], [131, 0, 0, 128]);
+    assert_eq!(top_mipmap_pixels(&open("bc2_dxt2"))[..4], [124, 0, 0, 136]);
+}
+
+#[test]
+#[serial]
+fn edds_bc5_test() {
+    let open = |name: &str, reconstruct_normal_z: bool| {
+        let file = File::open(format!("{}{}.edds", INPUT_PATH_PREFIX, name)).unwrap();
+        let mut options = DecodeOptions::default();
+        options.reconstruct_normal_z = reconstruct_normal_z;
+        Edds::from_with_options(&mut BufReader::new(file), &options).unwrap()
+    };
+
+    let rg = open("bc5_ati2", false);
+    export_mipmaps(&rg, "bc5_ati2", image::ColorType::Rgba8);
+    let top = top_mipmap_pixels(&rg);
+    assert_eq!(top[..4], [255, 128, 0, 255]);
+    assert_eq!(top[8..12], [128, 128, 0, 255]);
+
+    let normal = open("bc5_ati2", true);
+    export_mipmaps(&normal, "bc5_ati2_normal", image::ColorType::Rgba8);
+    let top = top_mipmap_pixels(&normal);
+    assert_eq!(top[..4], [255, 128, 128, 255]);
+    assert_eq!(top[8..12], [128, 128, 255, 255]);
+
+    let snorm = open("bc5_snorm", true);
+    export_mipmaps(&snorm, "bc5_snorm", image::ColorType::Rgba8);
+    let top = top_mipmap_pixels(&snorm);
+    assert_eq!(top[..4], [255, 128, 128, 255]);
+    assert_eq!(top[8..12], [128, 128, 255, 255]);
+}
+
+#[test]
+#[serial]
+fn edds_bc6h_test() {
+    let file = File::open(format!("{}bc6h_uf16.edds", INPUT_PATH_PREFIX)).unwrap();
+    let edds = Edds::from(&mut BufReader::new(file)).unwrap();
+
+    let top = edds
+        .mipmaps
+        .last()
+        .unwrap()
+        .data()
+        .unwrap()
+        .as_f32()
+        .unwrap();
+    assert_eq!(top.len(), 4 * 4 * 3);
+    assert_eq!(top[..6], [1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+
+    let file = File::open(format!("{}bc6h_sf16.edds", INPUT_PATH_PREFIX)).unwrap();
+    let edds = Edds::from(&mut BufReader::new(file)).unwrap();
+
+    let top = edds
+        .mipmaps
+        .last()
+        .unwrap()
+        .data()
+        .unwrap()
+        .as_f32()
+        .unwrap();
+    assert!(top[0] < -0.9 && top[0] > -1.0);
+    assert!(top[4] > 0.9 && top[4] < 1.0);
+}
+
+#[test]
+#[serial]
+fn edds_uncompressed_test() {
+    let open = |name: &str| {
+        let file = File::open(format!("{}{}.edds", INPUT_PATH_PREFIX, name)).unwrap();
+        Edds::from(&mut BufReader::new(file)).unwrap()
+    };
+
+    assert_eq!(
+        top_mipmap_pixels(&open("r8g8b8a8_snorm"))[..4],
+        [0, 128, 255, 255]
+    );
+    assert_eq!(top_mipmap_pixels(&open("b5g6r5"))[..4], [255, 0, 0, 255]);
+    assert_eq!(top_mipmap_pixels(&open("r8")), [7, 7, 7, 7]);
+
+    let edds = open("r10g10b10a2");
+    let top = edds
+        .mipmaps
+        .last()
+        .unwrap()
+        .data()
+        .unwrap()
+        .as_u16()
+        .unwrap();
+    assert_eq!(top[..4], [0, 65535, 0, 65535]);
+
+    let edds = open("r16g16b16a16_float");
+    let top = edds
+        .mipmaps
+        .last()
+        .unwrap()
+        .data()
+        .unwrap()
+        .as_f32()
+        .unwrap();
+    assert_eq!(top[..4], [1.0, 0.5, 0.0, 1.0]);
+
+    let edds = open("r11g11b10_float");
+    let top = edds
+        .mipmaps
+        .last()
+        .unwrap()
+        .data()
+        .unwrap()
+        .as_f32()
+        .unwrap();
+    assert_eq!(top.len(), 2 * 2 * 3);
+    assert_eq!(top[..3], [1.0, 1.0, 1.0]);
+
+    let edds = open("r9g9b9e5");
+    let top = edds
+        .mipmaps
+        .last()
+        .unwrap()
+        .data()
+        .unwrap()
+        .as_f32()
+        .unwrap();
+    assert_eq!(top[..3], [1.0, 0.5, 0.0]);
+}
+
+#[test]
+#[serial]
+fn edds_legacy_pixel_format_test() {
+    let open = |name: &str| {
+        let file = File::open(format!("{}legacy_{}.edds", INPUT_PATH_PREFIX, name)).unwrap();
+        Edds::from(&mut BufReader::new(file)).unwrap()
+    };
+
+    assert_eq!(top_mipmap_pixels(&open("a1r5g5b5"))[..4], [0, 0, 255, 255]);
+    assert_eq!(
+        top_mipmap_pixels(&open("r8g8b8"))[..4],
+        [0x10, 0x20, 0x30, 255]
+    );
+    assert_eq!(
+        top_mipmap_pixels(&open("a8l8"))[..4],
+        [0x40, 0x40, 0x40, 0x80]
+    );
+    assert_eq!(
+        top_mipmap_pixels(&open("a4l4"))[..4],
+        [0x55, 0x55, 0x55, 0xFF]
+    );
+    assert_eq!(
+        top_mipmap_pixels(&open("a4b4g4r4"))[..4],
+        [0, 0xFF, 0, 0x88]
+    );
+
+    let edds = open("l16");
+    assert_eq!(
+        edds.mipmaps
+            .last()
+            .unwrap()
+            .data()
+            .unwrap()
+            .as_u16()
+            .unwrap()[0],
+        0x1234
+    );
+
+    let edds = open("a2r10g10b10");
+    let top = edds
+        .mipmaps
+        .last()
+        .unwrap()
+        .data()
+        .unwrap()
+        .as_u16()
+        .unwrap();
+    assert_eq!(top[..4], [65535, 0, 0, 65535]);
+
+    let edds = open("g16r16");
+    let top = edds
+        .mipmaps
+        .last()
+        .unwrap()
+        .data()
+        .unwrap()
+        .as_u16()
+        .unwrap();
+    assert_eq!(top[..4], [65535, 0, 0, 65535]);
+
+    // A4B4G4R4 matches no known format and is sized from its bit count.
+    let path = format!("{}legacy_a4b4g4r4.edds", INPUT_PATH_PREFIX);
+    let original = std::fs::read(&path).unwrap();
+    let info = EddsInfo::from(&mut Cursor::new(&original)).unwrap();
+    assert_eq!(
+        StorageFormat::from_header(&info.header),
+        StorageFormat::Masked(16)
+    );
+    assert_eq!(info.mip_data_size(1), Some(8));
+    assert!(info.warnings.is_empty());
+
+    let mut dds = Vec::new();
+    edds_to_dds(&mut Cursor::new(&original), &mut dds).unwrap();
+    let raw = RawDds::from(&mut Cursor::new(&dds)).unwrap();
+    assert_eq!(
+        raw.mipmaps
+            .iter()
+            .map(|mipmap| mipmap.data.len())
+            .collect::<Vec<_>>(),
+        [8, 2]
+    );
+    let decoded = raw.decode(&DecodeOptions::default()).unwrap();
+    assert_eq!(
+        decoded.mipmaps[0].data().unwrap().as_u8().unwrap()[..4],
+        [0, 0xFF, 0, 0x88]
+    );
+
+    let mut short = RawEdds::from(&mut Cursor::new(&original)).unwrap();
+    short.mipmaps[1].data.truncate(6);
+    assert!(matches!(
+        short.decode(&DecodeOptions::default()),
+        Err(EddsError::MipDataTooShort(8, 6))
+    ));
+    assert!(matches!(
+        StorageFormat::Masked(12).data_size(2, 2),
+        Err(EddsError::UnknownImageDataFormat(_))
+    ));
+}
+
+#[test]
+#[serial]
+fn edds_bc4_variants_test() {
+    let open = |name: &str| {
+        let file = File::open(format!("{}{}.edds", INPUT_PATH_PREFIX, name)).unwrap();
+        let edds = Edds::from(&mut BufReader::new(file)).unwrap();
+        export_mipmaps(&edds, name, image::ColorType::L8);
+        edds
+    };
+
+    let snorm = open("bc4_snorm");
+    assert_eq!(top_mipmap_pixels(&snorm)[..4], [255, 255, 0, 0]);
+    assert_eq!(
+        top_mipmap_pixels(&open("bc4_bc4s")),
+        top_mipmap_pixels(&snorm)
+    );
+    assert_eq!(
+        top_mipmap_pixels(&open("bc4_ati1"))[..4],
+        [200, 200, 100, 100]
+    );
+}
+
+#[test]
+#[serial]
+fn edds_bc7_variants_test() {
+    let open = |name: &str| {
+        let file = File::open(format!("{}{}.edds", INPUT_PATH_PREFIX, name)).unwrap();
+        let edds = Edds::from(&mut BufReader::new(file)).unwrap();
+        export_mipmaps(&edds, name, image::ColorType::Rgba8);
+        edds
+    };
+
+    let srgb = open("bc7_srgb");
+    let unorm = open("bc7_unorm");
+    let typeless = open("bc7_typeless");
+
+    for ((srgb, unorm), typeless) in srgb
+        .mipmaps
+        .iter()
+        .zip(unorm.mipmaps.iter())
+        .zip(typeless.mipmaps.iter())
+    {
+        assert_eq!(srgb.surfaces, unorm.surfaces);
+        assert_eq!(srgb.surfaces, typeless.surfaces);
+        assert_eq!(srgb.format.color_space, ColorSpace::Srgb);
+        assert_eq!(unorm.format.color_space, ColorSpace::Linear);
+        assert_eq!(typeless.format.color_space, ColorSpace::Unspecified);
+    }
+}
+
+#[test]
+#[serial]
+fn edds_decoded_format_test() {
+    for entry in std::fs::read_dir(INPUT_PATH_PREFIX).unwrap() {
+        let path = entry.unwrap().path();
+        let file = File::open(&path).unwrap();
+        let edds = Edds::from(&mut BufReader::new(file)).unwrap();
+
+        assert_eq!(edds.mipmaps.len(), edds.blocks.len(), "{:?}", path);
+        for mipmap in &edds.mipmaps {
+            assert_eq!(mipmap.format, edds.format, "{:?}", path);
+            assert_eq!(
+                mipmap.surfaces.len(),
+                edds.layout.surface_count(),
+                "{:?}",
+                path
+            );
+
+            for data in &mipmap.surfaces {
+                let len = match (data, mipmap.format.bit_depth) {
+                    (PixelData::U8(data), 8) => data.len(),
+                    (PixelData::U16(data), 16) => data.len(),
+                    (PixelData::F32(data), 32) => data.len(),
+                    (data, bit_depth) => panic!("{:?}: {} bit {:?}", path, bit_depth, data),
+                };
+                assert_eq!(
+                    len,
+                    mipmap.width * mipmap.height * mipmap.depth * mipmap.format.channels as usize,
+                    "{:?}",
+                    path
+                );
+                assert_eq!(
+                    mipmap.format.sample_type == SampleType::Float,
+                    matches!(data, PixelData::F32(_)),
+                    "{:?}",
+                    path
+                );
+            }
+        }
+    }
+
+    let open = |name: &str| {
+        let file = File::open(format!("{}{}.edds", INPUT_PATH_PREFIX, name)).unwrap();
+        Edds::from(&mut BufReader::new(file)).unwrap().format
+    };
+    assert_eq!(open("prop_bc4").channels, 1);
+    assert_eq!(open("car_bc7").channels, 4);
+    assert_eq!(open("bc6h_uf16").channels, 3);
+    assert_eq!(open("legacy_l16").bit_depth, 16);
+}
+
+#[test]
+#[serial]
+fn edds_header_test() {
+    let file = File::open(format!("{}car_bc7.edds", INPUT_PATH_PREFIX)).unwrap();
+    let edds = Edds::from(&mut BufReader::new(file)).unwrap();
+
+    let top_mipmap = edds.mipmaps.last().unwrap();
+    assert_eq!(edds.header.width as usize, top_mipmap.width);
+    assert_eq!(edds.header.height as usize, top_mipmap.height);
+    assert_eq!(edds.header.mip_map_count as usize, edds.blocks.len());
+    assert_eq!(
+        edds.header.dx10_header.as_ref().unwrap().dxgi_format,
+        DxgiFormat::DXGI_FORMAT_BC7_UNORM_SRGB
+    );
+
+    for (block, mipmap) in edds.blocks.iter().zip(edds.mipmaps.iter()) {
+        assert_eq!(block.data_type, mipmap.data_type);
+        assert_eq!(block.size, mipmap.compressed_data_size);
+    }
+
+    let file_size = std::fs::metadata(format!("{}car_bc7.edds", INPUT_PATH_PREFIX))
+        .unwrap()
+        .len();
+    assert_eq!(
+        edds.stored_size(),
+        file_size - 128 - 20 - 8 * edds.blocks.len() as u64
+    );
+}
+
+#[test]
+#[serial]
+fn edds_info_test() {
+    for name in ["car_bc7", "optic", "prop_bc4", "Eden_1337_normal"] {
+        let path = format!("{}{}.edds", INPUT_PATH_PREFIX, name);
+        let info = EddsInfo::from(&mut BufReader::new(File::open(&path).unwrap())).unwrap();
+        let edds = Edds::from(&mut BufReader::new(File::open(&path).unwrap())).unwrap();
+
+        assert_eq!(info.mip_count(), edds.mipmaps.len());
+        assert_eq!(info.stored_size(), edds.stored_size());
+        assert_eq!(info.width, edds.mipmaps.last().unwrap().width);
+        assert_eq!(info.height, edds.mipmaps.last().unwrap().height);
+
+        for (mipmap_info, mipmap) in info.mipmaps.iter().zip(edds.mipmaps.iter()) {
+            assert_eq!(mipmap_info.width, mipmap.width);
+            assert_eq!(mipmap_info.height, mipmap.height);
+            assert_eq!(mipmap_info.block.data_type, mipmap.data_type);
+        }
+
+        let last = info.mipmaps.last().unwrap();
+        assert_eq!(
+            last.offset + last.block.size as u64,
+            std::fs::metadata(&path).unwrap().len()
+        );
+    }
+
+    let file = File::open(format!("{}car_bc7.edds", INPUT_PATH_PREFIX)).unwrap();
+    let info = EddsInfo::from(&mut BufReader::new(file)).unwrap();
+    assert_eq!(
+        info.format,
+        StorageFormat::Dxgi(DxgiFormat::DXGI_FORMAT_BC7_UNORM_SRGB)
+    );
+}
+
+#[test]
+#[serial]
+fn edds_lazy_test() {
+    for name in ["car_bc7", "optic", "Eden_1337_normal"] {
+        let path = format!("{}{}.edds", INPUT_PATH_PREFIX, name);
+        let edds = Edds::from(&mut BufReader::new(File::open(&path).unwrap())).unwrap();
+        let mut lazy = LazyEdds::from(BufReader::new(File::open(&path).unwrap())).unwrap();
+
+        assert_eq!(lazy.mip_count(), edds.mipmaps.len());
+        assert_eq!(lazy.format, edds.format);
+
+        // Out of order on purpose, every mip is read from its own offset.
+        assert_eq!(
+            lazy.top_mipmap().unwrap().surfaces,
+            edds.mipmaps.last().unwrap().surfaces
+        );
+        for index in (0..lazy.mip_count()).rev() {
+            let mipmap = lazy.mipmap(index).unwrap();
+            assert_eq!(mipmap.width, edds.mipmaps[index].width);
+            assert_eq!(mipmap.surfaces, edds.mipmaps[index].surfaces);
+        }
+
+        assert!(lazy.mipmap(lazy.mip_count()).is_err());
+    }
+}
+
+#[test]
+#[serial]
+fn edds_raw_test() {
+    let path = format!("{}Eden_1337_normal.edds", INPUT_PATH_PREFIX);
+    let raw = RawEdds::from(&mut BufReader::new(File::open(&path).unwrap())).unwrap();
+    let edds = Edds::from(&mut BufReader::new(File::open(&path).unwrap())).unwrap();
+
+    assert_eq!(raw.mipmaps.len(), edds.mipmaps.len());
+    for (raw_mipmap, mipmap) in raw.mipmaps.iter().zip(edds.mipmaps.iter()) {
+        // BC blocks cover 4x4 pixels, small mips are padded to a full block.
+        let blocks = raw_mipmap.width.div_ceil(4) * raw_mipmap.height.div_ceil(4);
+        let block_size = raw_mipmap.data.len() / blocks;
+        assert!(block_size == 8 || block_size == 16);
+        assert_eq!(raw_mipmap.data.len(), blocks * block_size);
+
+        let decoded = raw_mipmap
+            .decode(&raw.header, &DecodeOptions::default())
+            .unwrap();
+        assert_eq!(decoded.surfaces, mipmap.surfaces);
+    }
+
+    let decoded = raw.decode(&DecodeOptions::default()).unwrap();
+    assert_eq!(decoded.blocks, edds.blocks);
+}
+
+fn gradient_rgba8(width: usize, height: usize) -> Vec<u8> {
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            rgba.extend([
+                (x * 255 / width) as u8,
+                (y * 255 / height) as u8,
+                ((x + y) % 256) as u8,
+                (255 - x % 128) as u8,
+            ]);
+        }
+    }
+    rgba
+}
+
+#[test]
+#[serial]
+fn edds_write_round_trip_test() {
+    for name in ["bc7_srgb", "b5g6r5", "legacy_a8l8"] {
+        let bytes = std::fs::read(format!("{}{}.edds", INPUT_PATH_PREFIX, name)).unwrap();
+        let raw = RawEdds::from(&mut Cursor::new(&bytes)).unwrap();
+
+        let mut written = Vec::new();
+        raw.write(&mut written).unwrap();
+        assert_eq!(written, bytes, "{}", name);
+    }
+
+    for name in ["car_bc7", "optic", "Eden_1337_normal"] {
+        let path = format!("{}{}.edds", INPUT_PATH_PREFIX, name);
+        let raw = RawEdds::from(&mut BufReader::new(File::open(&path).unwrap())).unwrap();
+
+        let mut written = Vec::new();
+        raw.write(&mut written).unwrap();
+        let reread = RawEdds::from(&mut Cursor::new(&written)).unwrap();
+
+        assert_eq!(reread.header, raw.header);
+        for (reread, raw) in reread.mipmaps.iter().zip(raw.mipmaps.iter()) {
+            assert_eq!(reread.data_type, raw.data_type);
+            assert_eq!(reread.data, raw.data);
+        }
+    }
+}
+
+#[test]
+#[serial]
+fn edds_write_rgba8_test() {
+    let (width, height) = (37, 21);
+    let rgba = gradient_rgba8(width, height);
+
+    for legacy_header in [false, true] {
+        for compression in [Compression::None, Compression::Lz4] {
+            let mut options = EncodeOptions::default();
+            options.legacy_header = legacy_header;
+            options.compression = compression;
+            let raw = RawEdds::from_rgba8(width, height, &rgba, &options).unwrap();
+            let mut written = Vec::new();
+            raw.write(&mut written).unwrap();
+
+            let edds = Edds::from(&mut Cursor::new(&written)).unwrap();
+            assert_eq!(edds.header.dx10_header.is_some(), !legacy_header);
+            assert_eq!(edds.mipmaps.len(), 6);
+            assert_eq!(top_mipmap_pixels(&edds), rgba);
+
+            let smallest = &edds.mipmaps[0];
+            assert_eq!((smallest.width, smallest.height), (1, 1));
+        }
+    }
+
+    // Block compressed formats get a FourCC header and decode like their DX10 twin.
+    for (format, four_cc) in [
+        (DxgiFormat::DXGI_FORMAT_BC1_UNORM, FourCCEnum::DXT1),
+        (DxgiFormat::DXGI_FORMAT_BC3_UNORM, FourCCEnum::DXT5),
+        (DxgiFormat::DXGI_FORMAT_BC4_UNORM, FourCCEnum::ATI1),
+        (DxgiFormat::DXGI_FORMAT_BC5_UNORM, FourCCEnum::ATI2),
+    ] {
+        let encode = |legacy_header| {
+            let mut options = EncodeOptions::default();
+            options.format = format;
+            options.quality = EncodeQuality::Fast;
+            options.legacy_header = legacy_header;
+            RawDds::from_rgba8(width, height, &rgba, &options).unwrap()
+        };
+        let (legacy, dx10) = (encode(true), encode(false));
+        assert_eq!(legacy.header.ddspf.four_cc, four_cc);
+        assert!(legacy.header.dx10_header.is_none());
+
+        let mut written = Vec::new();
+        legacy.write(&mut written).unwrap();
+        let raw = RawDds::from(&mut Cursor::new(&written)).unwrap();
+        assert_eq!(raw.header, legacy.header, "{:?}", format);
+        assert_eq!(
+            StorageFormat::from_header(&raw.header),
+            StorageFormat::FourCC(four_cc)
+        );
+        let options = DecodeOptions::default();
+        let (legacy, dx10) = (
+            raw.decode(&options).unwrap(),
+            dx10.decode(&options).unwrap(),
+        );
+        assert_eq!(legacy.mipmaps.len(), dx10.mipmaps.len());
+        for (legacy, dx10) in legacy.mipmaps.iter().zip(dx10.mipmaps.iter()) {
+            assert_eq!(legacy.surfaces, dx10.surfaces, "{:?}", format);
+        }
+    }
+    let mut options = EncodeOptions::default();
+    options.format = DxgiFormat::DXGI_FORMAT_BC7_UNORM;
+    options.legacy_header = true;
+    assert!(matches!(
+        RawEdds::from_rgba8(width, height, &rgba, &options),
+        Err(EddsError::UnsupportedEncodeFormat(_))
+    ));
+
+    assert!(RawEdds::from_rgba8(width, height, &rgba[4..], &EncodeOptions::default()).is_err());
+    assert!(matches!(
+        RawEdds::from_rgba8(usize::MAX, 2, &[], &EncodeOptions::default()),
+        Err(EddsError::ImageTooLarge(..))
+    ));
+
+    for (width, height) in [(0, 5), (5, 0), (0, 0)] {
+        assert!(matches!(
+            RawEdds::from_rgba8(width, height, &[], &EncodeOptions::default()),
+            Err(EddsError::InvalidImageSize(w, h)) if (w, h) == (width, height)
+        ));
+        assert!(matches!(
+            generate_mipmaps(&[], width, height, &MipmapOptions::default(), false),
+            Err(EddsError::InvalidImageSize(w, h)) if (w, h) == (width, height)
+        ));
+    }
+
+    // Buffers that don't match the size are rejected before resampling.
+    let options = MipmapOptions::default();
+    assert!(matches!(
+        generate_mipmaps(&rgba[4..], width, height, &options, false),
+        Err(EddsError::PixelDataSizeMismatch(expected, actual))
+            if expected == rgba.len() && actual == rgba.len() - 4
+    ));
+    assert!(matches!(
+        generate_mipmaps(&rgba, width + 1, height, &options, false),
+        Err(EddsError::PixelDataSizeMismatch(..))
+    ));
+    assert!(matches!(
+        generate_mipmaps(&[], usize::MAX, 2, &options, false),
+        Err(EddsError::ImageTooLarge(..))
+    ));
+}
+
+/// Pixel values as bits, so that NaNs of float formats compare equal.
+fn pixel_bits(data: &PixelData) -> Vec<u32> {
+    match data {
+        PixelData::U8(data) => data.iter().map(|&value| value as u32).collect(),
+        PixelData::U16(data) => data.iter().map(|&value| value as u32).collect(),
+        PixelData::F32(data) => data.iter().map(|value| value.to_bits()).collect(),
+    }
+}
+
+/// Writes `data` as the only mip of a `width` x `height` file with `header` and
+/// checks every decoded pixel against the same data decoded at the size of its
+/// whole blocks, or as a single row for formats without blocks.
+fn assert_small_mip(name: &str, header: &DdsHeader, data: &[u8], width: usize, height: usize) {
+    let raw_mipmap = |width: usize, height: usize| RawMipmap {
+        width,
+        height,
+        depth: 1,
+        data_type: MipmapType::COPY,
+        compressed_data_size: data.len() as u32,
+        data: data.to_vec(),
+    };
+
+    let mut written = Vec::new();
+    RawEdds {
+        header: header.clone(),
+        mipmaps: vec![raw_mipmap(width, height)],
+    }
+    .write(&mut written)
+    .unwrap();
+    let edds = Edds::from(&mut Cursor::new(&written)).unwrap();
+    let mipmap = edds.mipmaps.last().unwrap();
+    assert_eq!((mipmap.width, mipmap.height), (width, height), "{}", name);
+    let actual = pixel_bits(mipmap.data().unwrap());
+
+    let (block_width, _) = StorageFormat::from_header(header).block_layout().unwrap();
+    let (stride, padded) = if block_width == 1 {
+        (width, raw_mipmap(width * height, 1))
+    } else {
+        let stride = width.div_ceil(block_width) * block_width;
+        let padded_height = height.div_ceil(block_width) * block_width;
+        (stride, raw_mipmap(stride, padded_height))
+    };
+    let padded = padded.decode(header, &DecodeOptions::default()).unwrap();
+    let expected = pixel_bits(padded.data().unwrap());
+
+    let channels = mipmap.format.channels as usize;
+    assert_eq!(actual.len(), width * height * channels, "{}", name);
+    for y in 0..height {
+        for x in 0..width {
+            let pixel =
+                |stride: usize| (y * stride + x) * channels..(y * stride + x + 1) * channels;
+            assert_eq!(
+                actual[pixel(width)],
+                expected[pixel(stride)],
+                "{} {}x{} at ({}, {})",
+                name,
+                width,
+                height,
+                x,
+                y
+            );
+        }
+    }
+}
+
+#[test]
+#[serial]
+fn edds_small_mip_test() {
+    // Sizes below a block and sizes that aren't a multiple of it, the block
+    // formats store these padded to whole 4x4 blocks.
+    let sizes = [(1, 1), (2, 2), (3, 5)];
+
+    // Lossless formats give back the gradient at every coordinate.
+    for format in [
+        DxgiFormat::DXGI_FORMAT_R8G8B8A8_UNORM,
+        DxgiFormat::DXGI_FORMAT_B8G8R8A8_UNORM,
+        DxgiFormat::DXGI_FORMAT_B8G8R8X8_UNORM,
+    ] {
+        let channels = match format {
+            DxgiFormat::DXGI_FORMAT_B8G8R8X8_UNORM => 3,
+            _ => 4,
+        };
+        for (width, height) in sizes {
+            let rgba = gradient_rgba8(width, height);
+            let mut options = EncodeOptions::default();
+            options.format = format;
+            options.mipmaps = None;
+            let raw = RawEdds::from_rgba8(width, height, &rgba, &options).unwrap();
+            let mut written = Vec::new();
+            raw.write(&mut written).unwrap();
+
+            let edds = Edds::from(&mut Cursor::new(&written)).unwrap();
+            let mipmap = edds.mipmaps.last().unwrap();
+            assert_eq!((mipmap.width, mipmap.height), (width, height));
+            let pixels = mipmap.data().unwrap().as_u8().unwrap();
+            assert_eq!(pixels.len(), rgba.len());
+            for y in 0..height {
+                for x in 0..width {
+                    let i = (y * width + x) * 4;
+                    assert_eq!(
+                        pixels[i..i + channels],
+                        rgba[i..i + channels],
+                        "{:?} {}x{} at ({}, {})",
+                        format,
+                        width,
+                        height,
+                        x,
+                        y
+                    );
+                }
+            }
+        }
+    }
+
+    // Encoded gradients of the block formats.
+    for format in [
+        DxgiFormat::DXGI_FORMAT_BC1_UNORM,
+        DxgiFormat::DXGI_FORMAT_BC3_UNORM,
+        DxgiFormat::DXGI_FORMAT_BC4_UNORM,
+        DxgiFormat::DXGI_FORMAT_BC5_UNORM,
+        DxgiFormat::DXGI_FORMAT_BC7_UNORM,
+    ] {
+        for (width, height) in sizes {
+            let mut options = EncodeOptions::default();
+            options.format = format;
+            options.mipmaps = None;
+            let rgba = gradient_rgba8(width, height);
+            let raw = RawEdds::from_rgba8(width, height, &rgba, &options).unwrap();
+            let name = format!("{:?}", format);
+            assert_small_mip(&name, &raw.header, &raw.mipmaps[0].data, width, height);
+        }
+    }
+
+    // Every other decodable format, with DX10, FourCC and legacy mask headers,
+    // filled with noise so that no two neighbouring pixels are alike.
+    let dx10 = |format: DxgiFormat| {
+        (
+            format!("{:?}", format),
+            DdsPixelFormat::from_four_cc(FourCCEnum::DX10),
+            Some(DdsHeaderDX10::new_texture_2d(format)),
+        )
+    };
+    let four_cc = |four_cc: FourCCEnum| {
+        (
+            format!("{:?}", four_cc),
+            DdsPixelFormat::from_four_cc(four_cc),
+            None,
+        )
+    };
+    let masks = |name: &str, bits, r, g, b, a| {
+        (
+            name.to_string(),
+            DdsPixelFormat::from_masks(bits, r, g, b, a),
+            None,
+        )
+    };
+
+    let mut formats: Vec<_> = [
+        DxgiFormat::DXGI_FORMAT_BC1_UNORM,
+        DxgiFormat::DXGI_FORMAT_BC1_UNORM_SRGB,
+        DxgiFormat::DXGI_FORMAT_BC2_UNORM,
+        DxgiFormat::DXGI_FORMAT_BC3_UNORM,
+        DxgiFormat::DXGI_FORMAT_BC4_UNORM,
+        DxgiFormat::DXGI_FORMAT_BC4_SNORM,
+        DxgiFormat::DXGI_FORMAT_BC5_UNORM,
+        DxgiFormat::DXGI_FORMAT_BC5_SNORM,
+        DxgiFormat::DXGI_FORMAT_BC6H_UF16,
+        DxgiFormat::DXGI_FORMAT_BC6H_SF16,
+        DxgiFormat::DXGI_FORMAT_BC7_UNORM,
+        DxgiFormat::DXGI_FORMAT_R8G8B8A8_UNORM,
+        DxgiFormat::DXGI_FORMAT_R8G8B8A8_SNORM,
+        DxgiFormat::DXGI_FORMAT_B8G8R8A8_UNORM,
+        DxgiFormat::DXGI_FORMAT_B8G8R8X8_UNORM,
+        DxgiFormat::DXGI_FORMAT_R8G8_UNORM,
+        DxgiFormat::DXGI_FORMAT_R8G8_SNORM,
+        DxgiFormat::DXGI_FORMAT_A8_UNORM,
+        DxgiFormat::DXGI_FORMAT_B5G6R5_UNORM,
+        DxgiFormat::DXGI_FORMAT_B5G5R5A1_UNORM,
+        DxgiFormat::DXGI_FORMAT_B4G4R4A4_UNORM,
+        DxgiFormat::DXGI_FORMAT_R8_UNORM,
+        DxgiFormat::DXGI_FORMAT_R8_SNORM,
+        DxgiFormat::DXGI_FORMAT_R16_UNORM,
+        DxgiFormat::DXGI_FORMAT_R16G16_UNORM,
+        DxgiFormat::DXGI_FORMAT_R16G16B16A16_UNORM,
+        DxgiFormat::DXGI_FORMAT_R10G10B10A2_UNORM,
+        DxgiFormat::DXGI_FORMAT_R16_FLOAT,
+        DxgiFormat::DXGI_FORMAT_R16G16B16A16_FLOAT,
+        DxgiFormat::DXGI_FORMAT_R32_FLOAT,
+        DxgiFormat::DXGI_FORMAT_R32G32B32_FLOAT,
+        DxgiFormat::DXGI_FORMAT_R32G32B32A32_FLOAT,
+        DxgiFormat::DXGI_FORMAT_R11G11B10_FLOAT,
+        DxgiFormat::DXGI_FORMAT_R9G9B9E5_SHAREDEXP,
+    ]
+    .into_iter()
+    .map(dx10)
+    .collect();
+    formats.extend(
+        [
+            FourCCEnum::DXT1,
+            FourCCEnum::DXT2,
+            FourCCEnum::DXT3,
+            FourCCEnum::DXT4,
+            FourCCEnum::DXT5,
+            FourCCEnum::ATI1,
+            FourCCEnum::BC4U,
+            FourCCEnum::BC4S,
+            FourCCEnum::ATI2,
+            FourCCEnum::BC5U,
+            FourCCEnum::BC5S,
+        ]
+        .into_iter()
+        .map(four_cc),
+    );
+    formats.extend([
+        masks("A8R8G8B8", 32, 0xFF0000, 0xFF00, 0xFF, 0xFF000000),
+        masks("X8R8G8B8", 32, 0xFF0000, 0xFF00, 0xFF, 0),
+        masks("A8B8G8R8", 32, 0xFF, 0xFF00, 0xFF0000, 0xFF000000),
+        masks("R8G8B8", 24, 0xFF0000, 0xFF00, 0xFF, 0),
+        masks("R5G6B5", 16, 0xF800, 0x7E0, 0x1F, 0),
+        masks("A1R5G5B5", 16, 0x7C00, 0x3E0, 0x1F, 0x8000),
+        masks("A4R4G4B4", 16, 0xF00, 0xF0, 0xF, 0xF000),
+        masks("A2R10G10B10", 32, 0x3FF00000, 0xFFC00, 0x3FF, 0xC0000000),
+        masks("G16R16", 32, 0xFFFF, 0xFFFF0000, 0, 0),
+        masks("L8", 8, 0xFF, 0, 0, 0),
+        masks("L16", 16, 0xFFFF, 0, 0, 0),
+        masks("A8L8", 16, 0xFF, 0, 0, 0xFF00),
+        masks("A4L4", 8, 0xF, 0, 0, 0xF0),
+        masks("A8", 8, 0, 0, 0, 0xFF),
+    ]);
+
+    let mut state = 0x2545_f491_u32;
+    for (name, ddspf, dx10_header) in &formats {
+        for (width, height) in sizes {
+            let header = DdsHeader::new(
+                width as u32,
+                height as u32,
+                1,
+                ddspf.clone(),
+                dx10_header.clone(),
+            );
+            let size = StorageFormat::from_header(&header)
+                .data_size(width, height)
+                .unwrap();
+            let mut data: Vec<u8> = (0..size)
+                .map(|_| {
+                    state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                    (state >> 24) as u8
+                })
+                .collect();
+
+            // Keep the random blocks clear of the reserved BC6H and BC7 modes.
+            let dxgi_format = dx10_header.as_ref().map(|dx10| dx10.dxgi_format);
+            for block in data.chunks_exact_mut(16) {
+                match dxgi_format {
+                    Some(DxgiFormat::DXGI_FORMAT_BC6H_UF16 | DxgiFormat::DXGI_FORMAT_BC6H_SF16) => {
+                        block[0] &= 0xFC
+                    }
+                    Some(DxgiFormat::DXGI_FORMAT_BC7_UNORM) => block[0] |= 0x80,
+                    _ => {}
+                }
+            }
+
+            assert_small_mip(name, &header, &data, width, height);
+        }
+    }
+
+    // Expected pixels worked out from the stored bytes rather than by a decoder.
+    let decode_top = |format: DxgiFormat, data: &[u8], width: usize, height: usize| {
+        let header = DdsHeader::new(
+            width as u32,
+            height as u32,
+            1,
+            DdsPixelFormat::from_four_cc(FourCCEnum::DX10),
+            Some(DdsHeaderDX10::new_texture_2d(format)),
+        );
+        let mut written = Vec::new();
+        RawEdds {
+            header,
+            mipmaps: vec![RawMipmap {
+                width,
+                height,
+                depth: 1,
+                data_type: MipmapType::COPY,
+                compressed_data_size: data.len() as u32,
+                data: data.to_vec(),
+            }],
+        }
+        .write(&mut written)
+        .unwrap();
+        let edds = Edds::from(&mut Cursor::new(&written)).unwrap();
+        top_mipmap_pixels(&edds).to_vec()
+    };
+
+    for (width, height) in sizes {
+        // BGRA8 stores every pixel as its own 4 bytes in B, G, R, A order.
+        let bgra: Vec<u8> = (0..width * height)
+            .flat_map(|i| [i as u8, 0x40 + i as u8, 0x80 + i as u8, 0xC0 + i as u8])
+            .collect();
+        let pixels = decode_top(DxgiFormat::DXGI_FORMAT_B8G8R8A8_UNORM, &bgra, width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let i = y * width + x;
+                assert_eq!(
+                    pixels[i * 4..i * 4 + 4],
+                    [0x80 + i as u8, 0x40 + i as u8, i as u8, 0xC0 + i as u8],
+                    "BGRA8 {}x{} at ({}, {})",
+                    width,
+                    height,
+                    x,
+                    y
+                );
+            }
+        }
+
+        // BC1 blocks in 4 colour mode that only use the exact endpoints. Blocks
+        // alternate between red/blue and white/green, the index of a pixel
+        // depends on its position in the block.
+        let endpoints = |bx: usize, by: usize| {
+            if (bx + by) % 2 == 0 {
+                (
+                    (0xF800_u16, [255, 0, 0, 255]),
+                    (0x001F_u16, [0, 0, 255, 255]),
+                )
+            } else {
+                (
+                    (0xFFFF_u16, [255, 255, 255, 255]),
+                    (0x07E0_u16, [0, 255, 0, 255]),
+                )
+            }
+        };
+        let index = |x: usize, y: usize| ((x + 2 * y) % 3 == 0) as u32;
+        let (blocks_x, blocks_y) = (width.div_ceil(4), height.div_ceil(4));
+        let mut bc1 = Vec::new();
+        for by in 0..blocks_y {
+            for bx in 0..blocks_x {
+                let ((color0, _), (color1, _)) = endpoints(bx, by);
+                let indices = (0..16).fold(0_u32, |bits, i| bits | index(i % 4, i / 4) << (2 * i));
+                bc1.extend(color0.to_le_bytes());
+                bc1.extend(color1.to_le_bytes());
+                bc1.extend(indices.to_le_bytes());
+            }
+        }
+        let pixels = decode_top(DxgiFormat::DXGI_FORMAT_BC1_UNORM, &bc1, width, height);
+        assert_eq!(pixels.len(), width * height * 4);
+        for y in 0..height {
+            for x in 0..width {
+                let ((_, rgba0), (_, rgba1)) = endpoints(x / 4, y / 4);
+                let expected = match index(x % 4, y % 4) {
+                    0 => rgba0,
+                    _ => rgba1,
+                };
+                let i = (y * width + x) * 4;
+                assert_eq!(
+                    pixels[i..i + 4],
+                    expected,
+                    "BC1 {}x{} at ({}, {})",
+                    width,
+                    height,
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    // A 3x5 BC1 mip takes 1x2 blocks, anything shorter is an error.
+    let mut options = EncodeOptions::default();
+    options.format = DxgiFormat::DXGI_FORMAT_BC1_UNORM;
+    options.mipmaps = None;
+    let mut raw = RawEdds::from_rgba8(3, 5, &gradient_rgba8(3, 5), &options).unwrap();
+    assert_eq!(raw.mipmaps[0].data.len(), 2 * 8);
+    raw.mipmaps[0].data.truncate(8);
+    assert!(raw.decode_mipmap(0, &DecodeOptions::default()).is_err());
+}
+
+#[test]
+#[serial]
+fn edds_lz4_stream_test() {
+    // Incompressible noise repeated three times, only the dictionary shared
+    // between blocks lets the later copies compress.
+    let mut state = 0x1234_5678_u32;
+    let noise: Vec<u8> = (0..65536)
+        .map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 24) as u8
+        })
+        .collect();
+    let src = noise.repeat(3);
+
+    for level in [
+        Lz4Level::Fast(1),
+        Lz4Level::Fast(16),
+        Lz4Level::High(4),
+        Lz4Level::High(12),
+    ] {
+        let compressed = compress_lz4_mip(&src, level).unwrap();
+        assert!(compressed.len() < noise.len() * 11 / 10, "{:?}", level);
+        assert_eq!(decompress_lz4_mip(&compressed).unwrap(), src, "{:?}", level);
+    }
+
+    let path = format!("{}car_bc7.edds", INPUT_PATH_PREFIX);
+    let raw = RawEdds::from(&mut BufReader::new(File::open(&path).unwrap())).unwrap();
+    let mut written = Vec::new();
+    raw.write_with_level(&mut written, Lz4Level::High(9))
+        .unwrap();
+    let reread = RawEdds::from(&mut Cursor::new(&written)).unwrap();
+    for (reread, raw) in reread.mipmaps.iter().zip(raw.mipmaps.iter()) {
+        assert_eq!(reread.data, raw.data);
+    }
+
+    // Mips that LZ4 doesn't shrink are written as `COPY`.
+    let raw = RawEdds {
+        header: DdsHeader::new(
+            128,
+            128,
+            1,
+            DdsPixelFormat::from_four_cc(FourCCEnum::DX10),
+            Some(DdsHeaderDX10::new_texture_2d(
+                DxgiFormat::DXGI_FORMAT_R8G8B8A8_UNORM,
+            )),
+        ),
+        mipmaps: vec![RawMipmap {
+            width: 128,
+            height: 128,
+            depth: 1,
+            data_type: MipmapType::LZ4,
+            compressed_data_size: 0,
+            data: noise.clone(),
+        }],
+    };
+    let mut written = Vec::new();
+    raw.write(&mut written).unwrap();
+    let reread = RawEdds::from(&mut Cursor::new(&written)).unwrap();
+    assert_eq!(reread.mipmaps[0].data_type, MipmapType::COPY);
+    assert_eq!(reread.mipmaps[0].data, noise);
+}
+
+fn psnr(a: &[u8], b: &[u8]) -> f64 {
+    assert_eq!(a.len(), b.len());
+    let squared_error: f64 = a
+        .iter()
+        .zip(b.iter())
+        .map(|(&a, &b)| (a as f64 - b as f64).powi(2))
+        .sum();
+    let mse = squared_error / a.len() as f64;
+    10.0 * (255.0 * 255.0 / mse).log10()
+}
+
+fn select_channels(pixels: &[u8], channel_count: usize, channels: &[usize]) -> Vec<u8> {
+    pixels
+        .chunks_exact(channel_count)
+        .flat_map(|pixel| channels.iter().map(move |&channel| pixel[channel]))
+        .collect()
+}
+
+#[test]
+#[serial]
+fn edds_bcn_encode_test() {
+    let file = File::open(format!("{}uaz_rgba.edds", INPUT_PATH_PREFIX)).unwrap();
+    let edds = Edds::from(&mut BufReader::new(file)).unwrap();
+    let source = &edds.mipmaps[edds.mipmaps.len() - 2];
+    let (width, height) = (source.width, source.height);
+
+    // The texture is opaque, put a gradient into alpha.
+    let mut rgba = source.data().unwrap().as_u8().unwrap().to_vec();
+    for (i, pixel) in rgba.chunks_exact_mut(4).enumerate() {
+        pixel[3] = (i % width * 255 / width) as u8;
+    }
+
+    let encode = |format: DxgiFormat, quality: EncodeQuality| {
+        let mut options = EncodeOptions::default();
+        options.format = format;
+        options.quality = quality;
+        options.mipmaps = None;
+        let mut written = Vec::new();
+        RawEdds::from_rgba8(width, height, &rgba, &options)
+            .unwrap()
+            .write(&mut written)
+            .unwrap();
+        let edds = Edds::from(&mut Cursor::new(&written)).unwrap();
+        top_mipmap_pixels(&edds).to_vec()
+    };
+    let source_channels = |channels: &[usize]| select_channels(&rgba, 4, channels);
+
+    let bc1 = encode(DxgiFormat::DXGI_FORMAT_BC1_UNORM, EncodeQuality::Normal);
+    assert!(
+        psnr(
+            &select_channels(&bc1, 4, &[0, 1, 2]),
+            &source_channels(&[0, 1, 2])
+        ) > 30.0
+    );
+
+    let bc3 = encode(DxgiFormat::DXGI_FORMAT_BC3_UNORM, EncodeQuality::Normal);
+    assert!(psnr(&bc3, &rgba) > 30.0);
+
+    let bc4 = encode(DxgiFormat::DXGI_FORMAT_BC4_UNORM, EncodeQuality::Normal);
+    assert!(psnr(&bc4, &source_channels(&[0])) > 36.0);
+
+    let bc5 = encode(DxgiFormat::DXGI_FORMAT_BC5_UNORM, EncodeQuality::Normal);
+    assert!(
+        psnr(
+            &select_channels(&bc5, 4, &[0, 1]),
+            &source_channels(&[0, 1])
+        ) > 36.0
+    );
+
+    let bc7_fast = psnr(
+        &encode(DxgiFormat::DXGI_FORMAT_BC7_UNORM, EncodeQuality::Fast),
+        &rgba,
+    );
+    let bc7_slow = psnr(
+        &encode(DxgiFormat::DXGI_FORMAT_BC7_UNORM, EncodeQuality::Slow),
+        &rgba,
+    );
+    assert!(bc7_fast > 38.0);
+    assert!(bc7_slow >= bc7_fast);
+
+    let mut options = EncodeOptions::default();
+    options.format = DxgiFormat::DXGI_FORMAT_BC6H_UF16;
+    assert!(RawEdds::from_rgba8(width, height, &rgba, &options).is_err());
+}
+
+#[test]
+#[serial]
+fn edds_mipmap_generation_test() {
+    let filters = [MipFilter::Box, MipFilter::Kaiser, MipFilter::Lanczos];
+
+    for filter in filters {
+        for srgb in [false, true] {
+            let mut options = MipmapOptions::default();
+            options.filter = filter;
+            let rgba = [30, 140, 250, 77].repeat(37 * 21);
+            let levels = generate_mipmaps(&rgba, 37, 21, &options, srgb).unwrap();
+
+            let dimensions: Vec<_> = levels.iter().map(|l| (l.width, l.height)).collect();
+            assert_eq!(
+                dimensions,
+                [(37, 21), (18, 10), (9, 5), (4, 2), (2, 1), (1, 1)]
+            );
+            for level in &levels {
+                assert_eq!(
+                    level.rgba,
+                    [30, 140, 250, 77].repeat(level.width * level.height)
+                );
+            }
+        }
+    }
+
+    // Black and white average to 50% grey in linear light.
+    let checker = [
+        0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255,
+    ];
+    let options = MipmapOptions::default();
+    assert_eq!(
+        generate_mipmaps(&checker, 2, 2, &options, true).unwrap()[1].rgba,
+        [188, 188, 188, 255]
+    );
+    assert_eq!(
+        generate_mipmaps(&checker, 2, 2, &options, false).unwrap()[1].rgba,
+        [128, 128, 128, 255]
+    );
+
+    // 30% of the pixels are opaque, the rest is fully transparent.
+    let mut state = 12345_u32;
+    let mut cutout = Vec::new();
+    for _ in 0..64 * 64 {
+        state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        cutout.extend([128, 128, 128, if (state >> 8) % 10 < 3 { 255 } else { 0 }]);
+    }
+    let coverage = |level: &MipLevel| {
+        let covered = level.rgba.chunks_exact(4).filter(|p| p[3] > 127).count();
+        covered as f32 / (level.width * level.height) as f32
+    };
+
+    for filter in filters {
+        let mut preserved = MipmapOptions::default();
+        preserved.filter = filter;
+        preserved.alpha_coverage = Some(0.5);
+        let levels = generate_mipmaps(&cutout, 64, 64, &preserved, false).unwrap();
+        let target = coverage(&levels[0]);
+        for level in &levels[1..4] {
+            assert!((coverage(level) - target).abs() < 0.07, "{:?}", filter);
+        }
+
+        let mut unpreserved = MipmapOptions::default();
+        unpreserved.filter = filter;
+        unpreserved.alpha_coverage = None;
+        let levels = generate_mipmaps(&cutout, 64, 64, &unpreserved, false).unwrap();
+        assert!(coverage(&levels[2]) < 0.1, "{:?}", filter);
+    }
+}
+
+#[test]
+#[serial]
+fn edds_texture_layout_test() {
+    let open = |name: &str| {
+        let file = File::open(format!("{}{}.edds", INPUT_PATH_PREFIX, name)).unwrap();
+        Edds::from(&mut BufReader::new(file)).unwrap()
+    };
+
+    let edds = open("car_bc7");
+    assert_eq!(edds.layout.kind, TextureKind::Texture2D);
+    assert_eq!(edds.layout.surface_count(), 1);
+    assert_eq!(edds.mipmaps[0].surfaces.len(), 1);
+
+    let edds = open("cubemap_dx10");
+    assert_eq!(edds.layout.kind, TextureKind::Cubemap);
+    assert_eq!(edds.layout.surface_count(), 6);
+    assert_eq!(edds.mipmaps.len(), 2);
+    let face_colors = [
+        [255, 0, 0],
+        [0, 255, 0],
+        [0, 0, 255],
+        [255, 255, 0],
+        [0, 255, 255],
+        [255, 0, 255],
+    ];
+    for (mipmap, size) in edds.mipmaps.iter().zip([2, 4]) {
+        assert_eq!((mipmap.width, mipmap.height), (size, size));
+        assert_eq!(mipmap.surfaces.len(), 6);
+        assert!(matches!(mipmap.data(), Err(EddsError::MultipleSurfaces(6))));
+        for (surface, data) in mipmap.surfaces.iter().enumerate() {
+            let pixels = data.as_u8().unwrap();
+            assert_eq!(pixels.len(), size * size * 4);
+            assert_eq!(pixels[..3], face_colors[surface]);
+            assert_eq!(pixels[3], size as u8);
+        }
+    }
+    assert_eq!(edds.layout.face(1), Some(CubeFace::NegativeX));
+
+    let edds = open("cubemap_legacy");
+    assert_eq!(
+        edds.layout.faces,
+        [
+            CubeFace::PositiveX,
+            CubeFace::PositiveY,
+            CubeFace::NegativeZ
+        ]
+    );
+    let tops: Vec<&[u8]> = edds.mipmaps[0]
+        .surfaces
+        .iter()
+        .map(|data| &data.as_u8().unwrap()[..4])
+        .collect();
+    assert_eq!(
+        tops,
+        [[255, 0, 0, 255], [0, 0, 255, 255], [255, 0, 255, 255]]
+    );
+
+    let edds = open("array_bc1");
+    assert_eq!(edds.layout.kind, TextureKind::Texture2D);
+    assert_eq!(edds.layout.array_size, 3);
+    assert_eq!(edds.layout.layer(2), 2);
+    assert_eq!(edds.mipmaps.len(), 2);
+    let top = edds.mipmaps.last().unwrap();
+    assert_eq!(top.width, 8);
+    for (data, color) in top
+        .surfaces
+        .iter()
+        .zip([[255, 0, 0], [0, 255, 0], [0, 0, 255]])
+    {
+        assert!(data
+            .as_u8()
+            .unwrap()
+            .chunks_exact(4)
+            .all(|pixel| pixel[..3] == color[..]));
+    }
+
+    let edds = open("volume_r8");
+    assert_eq!(edds.layout.kind, TextureKind::Volume);
+    assert_eq!(edds.layout.depth, 4);
+    let depths: Vec<usize> = edds.mipmaps.iter().map(|mipmap| mipmap.depth).collect();
+    assert_eq!(depths, [1, 2, 4]);
+    let top = edds
+        .mipmaps
+        .last()
+        .unwrap()
+        .data()
+        .unwrap()
+        .as_u8()
+        .unwrap();
+    assert_eq!(top.len(), 4 * 4 * 4);
+    for (slice, pixels) in top.chunks_exact(4 * 4).enumerate() {
+        assert!(pixels.iter().all(|&value| value == slice as u8));
+    }
+
+    let file = File::open(format!("{}cubemap_dx10.edds", INPUT_PATH_PREFIX)).unwrap();
+    let mut lazy = LazyEdds::from(BufReader::new(file)).unwrap();
+    assert_eq!(lazy.info.layout.surface_count(), 6);
+    let mipmap = lazy.mipmap(1).unwrap();
+    assert_eq!(mipmap.surfaces.len(), 6);
+    assert_eq!(
+        mipmap.surfaces,
+        open("cubemap_dx10").mipmaps.last().unwrap().surfaces
+    );
+}
+
+#[test]
+#[serial]
+fn edds_truncated_and_corrupt_test() {
+    let (width, height) = (64, 48);
+    let mut options = EncodeOptions::default();
+    options.compression = Compression::Lz4;
+    // Identical rows so that LZ4 shrinks the top mip and it is stored as `LZ4 `.
+    let rgba = gradient_rgba8(width, 1).repeat(height);
+    let raw = RawEdds::from_rgba8(width, height, &rgba, &options).unwrap();
+    let mut written = Vec::new();
+    raw.write(&mut written).unwrap();
+    let data_start = 128 + 20 + 8 * raw.mipmaps.len();
+
+    // Every cut short file is an error, never a panic.
+    for len in 0..written.len() {
+        assert!(Edds::from(&mut Cursor::new(&written[..len])).is_err());
+    }
+
+    assert!(matches!(
+        Edds::from(&mut Cursor::new(&written[..100])),
+        Err(EddsError::TruncatedHeader {
+            offset: 0,
+            expected: 128,
+            actual: 100
+        })
+    ));
+    assert!(matches!(
+        Edds::from(&mut Cursor::new(&written[..140])),
+        Err(EddsError::TruncatedHeader { offset: 128, .. })
+    ));
+
+    let info = EddsInfo::from(&mut Cursor::new(&written)).unwrap();
+    let top = info.mipmaps.last().unwrap();
+    assert_eq!(top.block.data_type, MipmapType::LZ4);
+    match Edds::from(&mut Cursor::new(&written[..written.len() - 1])) {
+        Err(EddsError::TruncatedMip {
+            index,
+            offset,
+            expected,
+            actual,
+        }) => {
+            assert_eq!(index, info.mip_count() - 1);
+            assert_eq!(offset, top.offset);
+            assert_eq!(expected, top.block.size as usize);
+            assert_eq!(actual, expected - 1);
+        }
+        result => panic!("{:?}", result.map(|_| ())),
+    }
+
+    // A wrong uncompressed size in the LZ4 framing of the top mip.
+    let mut corrupt = written.clone();
+    corrupt[top.offset as usize] ^= 0x01;
+    match Edds::from(&mut Cursor::new(&corrupt)) {
+        Err(EddsError::MipDecompression { index, offset, .. }) => {
+            assert_eq!(index, info.mip_count() - 1);
+            assert_eq!(offset, top.offset);
+        }
+        result => panic!("{:?}", result.map(|_| ())),
+    }
+
+    // Flipped bits anywhere in the mip data may or may not decode, but must not panic.
+    for position in data_start..written.len() {
+        let mut corrupt = written.clone();
+        corrupt[position] ^= 0xA5;
+        let _ = Edds::from(&mut Cursor::new(&corrupt));
+    }
+
+    // A stored mip that is smaller than its dimensions require.
+    let mut short = raw.clone();
+    short.mipmaps.last_mut().unwrap().data.truncate(16);
+    short.mipmaps.last_mut().unwrap().data_type = MipmapType::COPY;
+    let mut written = Vec::new();
+    short.write(&mut written).unwrap();
+    match Edds::from(&mut Cursor::new(&written)) {
+        Err(EddsError::MipSizeMismatch {
+            index,
+            expected,
+            actual,
+            ..
+        }) => {
+            assert_eq!(index, short.mipmaps.len() - 1);
+            assert_eq!(expected, width * height * 4);
+            assert_eq!(actual, 16);
+        }
+        result => panic!("{:?}", result.map(|_| ())),
+    }
+
+    assert!(matches!(
+        decompress_lz4_mip(&[8, 0, 0, 0, 4, 0, 0]),
+        Err(EddsError::InvalidLz4Stream { .. })
+    ));
+}
+
+#[test]
+#[serial]
+fn edds_header_mode_test() {
+    let path = format!("{}bc1_dx10.edds", INPUT_PATH_PREFIX);
+    let original = std::fs::read(&path).unwrap();
+    let mut strict = DecodeOptions::default();
+    strict.header_mode = HeaderMode::Strict;
+    assert!(EddsInfo::from(&mut Cursor::new(&original))
+        .unwrap()
+        .warnings
+        .is_empty());
+    assert!(Edds::from_with_options(&mut Cursor::new(&original), &strict).is_ok());
+
+    let set_u32 = |bytes: &mut Vec<u8>, offset: usize, value: u32| {
+        bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    };
+    let get_u32 = |bytes: &[u8], offset: usize| {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    };
+
+    // Bits no flag enum knows about, as written by some third party exporters.
+    let mut unknown = original.clone();
+    set_u32(&mut unknown, 8, get_u32(&original, 8) | 0x4000_0000);
+    set_u32(&mut unknown, 80, get_u32(&original, 80) | 0x100);
+    set_u32(&mut unknown, 108, get_u32(&original, 108) | 0x2);
+    set_u32(&mut unknown, 112, get_u32(&original, 112) | 0x1);
+
+    let info = EddsInfo::from(&mut Cursor::new(&unknown)).unwrap();
+    assert_eq!(
+        info.warnings,
+        [
+            HeaderDiagnostic::UnknownBits {
+                field: HeaderField::Flags,
+                bits: 0x4000_0000
+            },
+            HeaderDiagnostic::UnknownBits {
+                field: HeaderField::PixelFormatFlags,
+                bits: 0x100
+            },
+            HeaderDiagnostic::UnknownBits {
+                field: HeaderField::Caps,
+                bits: 0x2
+            },
+            HeaderDiagnostic::UnknownBits {
+                field: HeaderField::Caps2,
+                bits: 0x1
+            },
+        ]
+    );
+    assert_eq!(info.header.to_stream_bytes().unwrap(), unknown[..128 + 20]);
+    let pixels = |bytes: &[u8]| -> Vec<PixelData> {
+        let edds = Edds::from(&mut Cursor::new(bytes)).unwrap();
+        edds.mipmaps
+            .into_iter()
+            .flat_map(|mipmap| mipmap.surfaces)
+            .collect()
+    };
+    assert_eq!(pixels(&unknown), pixels(&original));
+    assert!(matches!(
+        Edds::from_with_options(&mut Cursor::new(&unknown), &strict),
+        Err(EddsError::InvalidHeader(_))
+    ));
+
+    // DDS files are checked the same way.
+    let to_dds = |edds: &[u8]| {
+        let mut dds = Vec::new();
+        edds_to_dds(&mut Cursor::new(edds), &mut dds).unwrap();
+        dds
+    };
+    let (original_dds, unknown_dds) = (to_dds(&original), to_dds(&unknown));
+    assert!(Dds::from_with_options(&mut Cursor::new(&original_dds), &strict).is_ok());
+    assert!(Dds::from(&mut Cursor::new(&unknown_dds)).is_ok());
+    match Dds::from_with_options(&mut Cursor::new(&unknown_dds), &strict) {
+        Err(EddsError::InvalidHeader(diagnostics)) => assert_eq!(diagnostics, info.warnings),
+        result => panic!("{:?}", result.map(|_| ())),
+    }
+    assert!(matches!(
+        RawDds::from_with_mode(&mut Cursor::new(&unknown_dds), HeaderMode::Strict),
+        Err(EddsError::InvalidHeader(_))
+    ));
+
+    // Fields that contradict the rest of the header.
+    let mut inconsistent = original.clone();
+    set_u32(&mut inconsistent, 8, get_u32(&original, 8) & !0x20000);
+    set_u32(&mut inconsistent, 20, 7);
+    set_u32(&mut inconsistent, 108, 0x1000);
+
+    let lazy = LazyEdds::from(Cursor::new(&inconsistent)).unwrap();
+    let expected = [
+        HeaderDiagnostic::MissingFlag(DdsHeaderFlags::DDSD_MIPMAPCOUNT as u32),
+        HeaderDiagnostic::PitchOrLinearSize {
+            expected: 32,
+            actual: 7,
+        },
+        HeaderDiagnostic::MissingMipmapCaps { mip_map_count: 4 },
+    ];
+    assert_eq!(lazy.info.warnings, expected);
+    match LazyEdds::from_with_options(Cursor::new(&inconsistent), strict) {
+        Err(EddsError::InvalidHeader(diagnostics)) => assert_eq!(diagnostics, expected),
+        result => panic!("{:?}", result.map(|_| ())),
+    }
+}
+
+#[test]
+#[serial]
+fn dds_read_write_test() {
+    for name in [
+        "car_bc7",
+        "optic",
+        "bc1_dxt1",
+        "cubemap_dx10",
+        "array_bc1",
+        "volume_r8",
+    ] {
+        let path = format!("{}{}.edds", INPUT_PATH_PREFIX, name);
+        let original = std::fs::read(&path).unwrap();
+        let raw = RawEdds::from(&mut Cursor::new(&original)).unwrap();
+
+        let mut dds = Vec::new();
+        RawDds::from_edds(raw.clone()).write(&mut dds).unwrap();
+        let header_size = if raw.header.dx10_header.is_some() {
+            148
+        } else {
+            128
+        };
+        let data_size: usize = raw.mipmaps.iter().map(|mipmap| mipmap.data.len()).sum();
+        assert_eq!(dds.len(), header_size + data_size, "{}", name);
+
+        // Back to EDDS without touching the stored blocks.
+        let reread = RawDds::from(&mut Cursor::new(&dds)).unwrap();
+        let edds = reread.into_edds(Compression::None);
+        assert_eq!(edds.header, raw.header, "{}", name);
+        for (converted, raw) in edds.mipmaps.iter().zip(raw.mipmaps.iter()) {
+            assert_eq!(converted.data, raw.data, "{}", name);
+        }
+        if raw
+            .mipmaps
+            .iter()
+            .all(|mipmap| mipmap.data_type == MipmapType::COPY)
+        {
+            let mut written = Vec::new();
+            edds.write(&mut written).unwrap();
+            assert_eq!(written, original, "{}", name);
+        }
+
+        // Same pixels as the EDDS decoder, in largest first order.
+        let decoded = Dds::from(&mut Cursor::new(&dds)).unwrap();
+        let edds = Edds::from(&mut Cursor::new(&original)).unwrap();
+        assert_eq!(decoded.format, edds.format);
+        assert_eq!(decoded.layout, edds.layout);
+        assert_eq!(decoded.mipmaps.len(), edds.mipmaps.len());
+        for (a, b) in decoded.mipmaps.iter().zip(edds.mipmaps.iter().rev()) {
+            assert_eq!((a.width, a.height, a.depth), (b.width, b.height, b.depth));
+            assert_eq!(a.surfaces, b.surfaces, "{}", name);
+        }
+    }
+
+    // DDS stores every face with its whole mip chain before the next face.
+    let path = format!("{}cubemap_dx10.edds", INPUT_PATH_PREFIX);
+    let raw = RawEdds::from(&mut BufReader::new(File::open(path).unwrap())).unwrap();
+    let mut dds = Vec::new();
+    RawDds::from_edds(raw).write(&mut dds).unwrap();
+    assert_eq!(dds[..4], *b"DDS ");
+    let data = &dds[128 + 20..];
+    assert_eq!(data[..4], [255, 0, 0, 4]);
+    assert_eq!(data[4 * 4 * 4..][..4], [255, 0, 0, 2]);
+    assert_eq!(data[4 * 4 * 4 + 2 * 2 * 4..][..4], [0, 255, 0, 4]);
+
+    let rgba = gradient_rgba8(16, 8);
+    let mut options = EncodeOptions::default();
+    options.format = DxgiFormat::DXGI_FORMAT_BC1_UNORM;
+    let mut dds = Vec::new();
+    RawDds::from_rgba8(16, 8, &rgba, &options)
+        .unwrap()
+        .write(&mut dds)
+        .unwrap();
+    assert_eq!(dds.len(), 128 + 20 + (8 + 2 + 1 + 1 + 1) * 8);
+    let decoded = Dds::from(&mut Cursor::new(&dds)).unwrap();
+    assert_eq!(decoded.mipmaps.len(), 5);
+    assert_eq!(
+        (decoded.mipmaps[0].width, decoded.mipmaps[0].height),
+        (16, 8)
+    );
+
+    assert!(matches!(
+        Dds::from(&mut Cursor::new(&dds[..dds.len() - 1])),
+        Err(EddsError::TruncatedMip { index: 4, .. })
+    ));
+
+    // Header sizes only matter for the data that is actually there.
+    let header = |width, height, format| {
+        DdsHeader::new(
+            width,
+            height,
+            1,
+            DdsPixelFormat::from_four_cc(FourCCEnum::DX10),
+            Some(DdsHeaderDX10::new_texture_2d(format)),
+        )
+        .to_stream_bytes()
+        .unwrap()
+    };
+    let mut huge = header(65536, 65536, DxgiFormat::DXGI_FORMAT_R32G32B32A32_FLOAT);
+    huge.extend_from_slice(&[0; 64]);
+    assert!(matches!(
+        RawDds::from(&mut Cursor::new(&huge)),
+        Err(EddsError::TruncatedMip {
+            index: 0,
+            expected: 68719476736,
+            actual: 64,
+            ..
+        })
+    ));
+
+    let overflowing = header(
+        u32::MAX,
+        u32::MAX,
+        DxgiFormat::DXGI_FORMAT_R32G32B32A32_FLOAT,
+    );
+    assert!(matches!(
+        RawDds::from(&mut Cursor::new(&overflowing)),
+        Err(EddsError::ImageTooLarge(..))
+    ));
+}
+
+#[test]
+#[serial]
+fn edds_dds_repack_test() {
+    for name in ["car_bc7", "optic", "cubemap_dx10", "array_bc1", "volume_r8"] {
+        let path = format!("{}{}.edds", INPUT_PATH_PREFIX, name);
+        let original = std::fs::read(&path).unwrap();
+        let raw = RawEdds::from(&mut Cursor::new(&original)).unwrap();
+
+        let mut dds = Vec::new();
+        edds_to_dds(&mut Cursor::new(&original), &mut dds).unwrap();
+        let mut expected = Vec::new();
+        RawDds::from_edds(raw.clone()).write(&mut expected).unwrap();
+        assert_eq!(dds, expected, "{}", name);
+
+        for compression in [Compression::None, Compression::Lz4] {
+            let mut options = RepackOptions::default();
+            options.compression = compression;
+            let mut edds = Vec::new();
+            dds_to_edds(&mut Cursor::new(&dds), &mut edds, &options).unwrap();
+
+            let repacked = RawEdds::from(&mut Cursor::new(&edds)).unwrap();
+            assert_eq!(repacked.header, raw.header, "{}", name);
+            for (repacked, raw) in repacked.mipmaps.iter().zip(raw.mipmaps.iter()) {
+                assert_eq!(repacked.data, raw.data, "{}", name);
+                if compression == Compression::None {
+                    assert_eq!(repacked.data_type, MipmapType::COPY);
+                }
+            }
+
+            let all_copy = raw
+                .mipmaps
+                .iter()
+                .all(|mipmap| mipmap.data_type == MipmapType::COPY);
+            if compression == Compression::None && all_copy {
+                assert_eq!(edds, original, "{}", name);
+            }
+        }
+    }
+
+    // Tiny BC7 mips don't shrink with LZ4 and stay COPY, the top mip does.
+    let path = format!("{}car_bc7.edds", INPUT_PATH_PREFIX);
+    let mut dds = Vec::new();
+    edds_to_dds(&mut BufReader::new(File::open(path).unwrap()), &mut dds).unwrap();
+    let mut edds = Vec::new();
+    dds_to_edds(&mut Cursor::new(&dds), &mut edds, &RepackOptions::default()).unwrap();
+    let info = EddsInfo::from(&mut Cursor::new(&edds)).unwrap();
+    assert_eq!(info.mipmaps[0].block.data_type, MipmapType::COPY);
+    assert_eq!(info.mipmaps[0].block.size, 16);
+    assert_eq!(
+        info.mipmaps.last().unwrap().block.data_type,
+        MipmapType::LZ4
+    );
+    assert!(edds.len() < dds.len());
+}
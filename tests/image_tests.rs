@@ -0,0 +1,81 @@
+//! Tests of the `image` feature, run with `cargo test --features image`.
+
+use std::{fs::File, io::BufReader};
+
+use eff::edds::{Edds, EddsDecoder, EddsError};
+use image::{ColorType, DynamicImage, ImageDecoder};
+use serial_test::serial;
+
+const INPUT_PATH_PREFIX: &str = "./tests/test-data/edds_in/";
+
+#[test]
+#[serial]
+fn edds_image_decoder_test() {
+    let reader = |name: &str| {
+        let file = File::open(format!("{}{}.edds", INPUT_PATH_PREFIX, name)).unwrap();
+        BufReader::new(file)
+    };
+
+    for (name, color_type) in [
+        ("car_bc7", ColorType::Rgba8),
+        ("r8", ColorType::L8),
+        ("legacy_l16", ColorType::L16),
+        ("r11g11b10_float", ColorType::Rgb32F),
+        ("r16g16b16a16_float", ColorType::Rgba32F),
+    ] {
+        let edds = Edds::from(&mut reader(name)).unwrap();
+        let top = edds.mipmaps.last().unwrap();
+
+        let decoder = EddsDecoder::new(reader(name)).unwrap();
+        assert_eq!(decoder.color_type(), color_type, "{}", name);
+        assert_eq!(
+            decoder.dimensions(),
+            (top.width as u32, top.height as u32),
+            "{}",
+            name
+        );
+
+        let image = DynamicImage::from_decoder(decoder).unwrap();
+        assert_eq!(image.color(), color_type, "{}", name);
+        assert_eq!(image, top.to_dynamic_image().unwrap(), "{}", name);
+    }
+
+    let edds = Edds::from(&mut reader("car_bc7")).unwrap();
+    let image = edds.mipmaps.last().unwrap().to_dynamic_image().unwrap();
+    assert_eq!(
+        image.as_bytes(),
+        edds.mipmaps
+            .last()
+            .unwrap()
+            .data()
+            .unwrap()
+            .as_u8()
+            .unwrap()
+    );
+
+    let mut decoder = EddsDecoder::new(reader("car_bc7")).unwrap();
+    decoder.select_mipmap(0).unwrap();
+    assert_eq!(decoder.dimensions(), (1, 1));
+    assert!(matches!(
+        decoder.select_mipmap(edds.mipmaps.len()),
+        Err(EddsError::MipmapIndexOutOfRange(_, _))
+    ));
+
+    // Cubemaps only decode once a face is picked.
+    let decoder = EddsDecoder::new(reader("cubemap_dx10")).unwrap();
+    assert!(DynamicImage::from_decoder(decoder).is_err());
+    let mut decoder = EddsDecoder::new(reader("cubemap_dx10")).unwrap();
+    assert!(matches!(
+        decoder.select_surface(6),
+        Err(EddsError::SurfaceIndexOutOfRange(6, 6))
+    ));
+    decoder.select_surface(5).unwrap();
+    let image = DynamicImage::from_decoder(decoder).unwrap();
+    assert_eq!(image.as_bytes()[..4], [255, 0, 255, 4]);
+
+    // Only the first depth slice of a volume ends up in the image.
+    let edds = Edds::from(&mut reader("volume_r8")).unwrap();
+    let image = edds.mipmaps.last().unwrap().to_dynamic_image().unwrap();
+    assert_eq!((image.width(), image.height()), (4, 4));
+    assert!(image.as_bytes().iter().all(|&value| value == 0));
+}
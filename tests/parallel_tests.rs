@@ -0,0 +1,82 @@
+//! Tests of the `rayon` feature, run with `cargo test --features rayon`.
+
+use std::{fs::File, io::BufReader};
+
+use eff::edds::{
+    Compression, DecodeOptions, DxgiFormat, Edds, EddsError, EncodeOptions, EncodeQuality, RawEdds,
+};
+use serial_test::serial;
+
+const INPUT_PATH_PREFIX: &str = "./tests/test-data/edds_in/";
+
+#[test]
+#[serial]
+fn edds_parallel_decode_test() {
+    let options = DecodeOptions::default();
+
+    for name in [
+        "car_bc7",
+        "optic",
+        "Eden_1337_normal",
+        "Eden_1337_supertexture",
+        "uaz_rgba",
+        "legacy_l16",
+        "cubemap_dx10",
+        "array_bc1",
+        "volume_r8",
+    ] {
+        let path = format!("{}{}.edds", INPUT_PATH_PREFIX, name);
+        let raw = RawEdds::from(&mut BufReader::new(File::open(&path).unwrap())).unwrap();
+
+        let serial = raw.decode(&options).unwrap();
+        let parallel = raw.par_decode(&options).unwrap();
+        assert_eq!(parallel.layout, serial.layout, "{}", name);
+        assert_eq!(parallel.mipmaps.len(), serial.mipmaps.len(), "{}", name);
+        for (parallel, serial) in parallel.mipmaps.iter().zip(serial.mipmaps.iter()) {
+            assert_eq!(
+                (parallel.width, parallel.height, parallel.depth),
+                (serial.width, serial.height, serial.depth),
+                "{}",
+                name
+            );
+            assert_eq!(parallel.surfaces, serial.surfaces, "{}", name);
+        }
+
+        let edds = Edds::par_from(&mut BufReader::new(File::open(&path).unwrap())).unwrap();
+        assert_eq!(edds.mipmaps.len(), serial.mipmaps.len(), "{}", name);
+    }
+
+    // A truncated mip fails the same way as with the single threaded decoder.
+    let path = format!("{}car_bc7.edds", INPUT_PATH_PREFIX);
+    let mut raw = RawEdds::from(&mut BufReader::new(File::open(path).unwrap())).unwrap();
+    raw.mipmaps.last_mut().unwrap().data.truncate(1000);
+    assert!(matches!(
+        raw.par_decode(&options),
+        Err(EddsError::MipDataTooShort(4194304, 1000))
+    ));
+
+    // The last band of a BC1 surface whose height isn't a multiple of the
+    // band height is cropped to the rows that are left.
+    let (width, height) = (2048, 2050);
+    let rgba: Vec<u8> = (0..width * height)
+        .flat_map(|i| {
+            let (x, y) = (i % width, i / width);
+            [x as u8, y as u8, (x / 256 + y / 256 * 8) as u8, 255]
+        })
+        .collect();
+    let mut encode_options = EncodeOptions::default();
+    encode_options.format = DxgiFormat::DXGI_FORMAT_BC1_UNORM;
+    encode_options.quality = EncodeQuality::Fast;
+    encode_options.mipmaps = None;
+    encode_options.compression = Compression::None;
+    let raw = RawEdds::from_rgba8(width, height, &rgba, &encode_options).unwrap();
+    let serial = raw.decode(&options).unwrap();
+    let parallel = raw.par_decode(&options).unwrap();
+    let (serial, parallel) = (&serial.mipmaps[0], &parallel.mipmaps[0]);
+    assert_eq!((parallel.width, parallel.height), (width, height));
+    assert_eq!(
+        parallel.data().unwrap().as_u8().unwrap().len(),
+        width * height * 4
+    );
+    assert_eq!(parallel.surfaces, serial.surfaces);
+}